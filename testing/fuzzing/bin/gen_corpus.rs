@@ -0,0 +1,211 @@
+// Dictionary and Seed-Corpus Generator
+//
+// libFuzzer's coverage-guided mutation has to rediscover every magic
+// constant a validator branches on (syscall numbers, IPC message types,
+// the allocator's size cap, the various address/length boundary values)
+// by brute force unless it's told about them up front. This tool writes
+// both artifacts straight from the targets' own validators instead of
+// leaving that rediscovery to chance:
+//   - a libFuzzer dictionary (`name="value"` entries, `cargo fuzz run
+//     <target> -- -dict=neuro_tools.dict`) listing those constants so the
+//     mutator can splice them in directly;
+//   - a seed corpus of minimal, well-formed inputs for each target, so a
+//     fuzzing session starts from "parses and reaches the interesting
+//     branch" instead of "empty input, reject immediately".
+//
+// Usage: cargo run --bin gen_corpus -- <output_dir>
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// One dictionary entry: `name="value"`, where `value` may contain `\xNN`
+/// escapes for non-printable bytes, per libFuzzer's dictionary format.
+struct DictEntry {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+/// Escapes `bytes` as a libFuzzer dictionary value: printable ASCII
+/// passes through except `"` and `\`, which are backslash-escaped, and
+/// everything else (including the constants here, which are mostly raw
+/// little-endian integers) becomes `\xNN`.
+fn escape_dict_value(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 4 + 2);
+    out.push('"');
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02X}", b)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The magic constants `fuzz_syscall_dispatcher` and `fuzz_ipc_message`
+/// branch on directly, mirroring `SYSCALL_BOUNDARY_VALUES`,
+/// `VALID_SYSCALL_NUMBERS`, and `IPC_PAYLOAD_LEN_BOUNDARY_VALUES` in
+/// `lib.rs`'s custom mutator, plus the allocator's size cap from
+/// `fuzz_allocator`.
+fn build_dictionary() -> Vec<DictEntry> {
+    let mut entries = Vec::new();
+
+    let syscall_numbers: [u64; 4] = [0, 1, 2, 9];
+    for (i, n) in syscall_numbers.into_iter().enumerate() {
+        entries.push(DictEntry { name: format!("syscall_num_{i}"), bytes: n.to_le_bytes().to_vec() });
+    }
+
+    let ipc_msg_types: [u32; 3] = [0, 1, 2];
+    for (i, t) in ipc_msg_types.into_iter().enumerate() {
+        entries.push(DictEntry { name: format!("ipc_msg_type_{i}"), bytes: t.to_le_bytes().to_vec() });
+    }
+
+    entries.push(DictEntry { name: "ipc_num_args_boundary".to_string(), bytes: 16u32.to_le_bytes().to_vec() });
+    entries.push(DictEntry { name: "allocator_size_cap".to_string(), bytes: 0x100000u64.to_le_bytes().to_vec() });
+
+    let syscall_boundary_values: [u64; 5] = [0, u64::MAX, 0xFFF, 0x7FFFF000, 0x0000_8000_0000_0000];
+    for (i, v) in syscall_boundary_values.into_iter().enumerate() {
+        entries.push(DictEntry { name: format!("syscall_boundary_{i}"), bytes: v.to_le_bytes().to_vec() });
+    }
+
+    let ipc_payload_len_boundary_values: [u32; 4] = [0, u32::MAX, 0x100000, 16];
+    for (i, v) in ipc_payload_len_boundary_values.into_iter().enumerate() {
+        entries.push(DictEntry { name: format!("ipc_payload_len_boundary_{i}"), bytes: v.to_le_bytes().to_vec() });
+    }
+
+    entries
+}
+
+fn write_dictionary(path: &Path) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    for entry in build_dictionary() {
+        writeln!(file, "{}={}", entry.name, escape_dict_value(&entry.bytes))?;
+    }
+    Ok(())
+}
+
+/// A minimal `read(fd, buf, count)` syscall blob that clears every
+/// validation branch in `fuzz_syscall_dispatcher`: a valid fd backed by a
+/// tail fd-table bitmap with that bit set, an in-range user-space
+/// address, and a count under the cap.
+fn valid_read_syscall_seed() -> Vec<u8> {
+    let mut blob = Vec::new();
+    blob.extend_from_slice(&0u64.to_le_bytes()); // syscall_num: read
+
+    let fd = 3u64;
+    let args: [u64; 6] = [fd, 0x1000, 64, 0, 0, 0]; // fd, buf_addr, count, unused...
+    for arg in args {
+        blob.extend_from_slice(&arg.to_le_bytes());
+    }
+
+    // Tail: fd-table bitmap with fd 3's bit set, so `fd_is_open` accepts it.
+    blob.extend_from_slice(&(1u32 << fd).to_le_bytes());
+    blob
+}
+
+/// One minimal, well-formed blob per `fuzz_ipc_message` variant, each with
+/// `payload_len` matching its actual payload so the message isn't
+/// rejected as truncated.
+fn ipc_message_seeds() -> Vec<(&'static str, Vec<u8>)> {
+    let mut seeds = Vec::new();
+
+    // msg_type 0: simple notification, empty payload.
+    let mut notification = Vec::new();
+    notification.extend_from_slice(&0u32.to_le_bytes());
+    notification.extend_from_slice(&0u32.to_le_bytes());
+    seeds.push(("notification", notification));
+
+    // msg_type 1: data transfer, payload is just a 4-byte data_id.
+    let mut data_transfer = Vec::new();
+    data_transfer.extend_from_slice(&1u32.to_le_bytes());
+    data_transfer.extend_from_slice(&4u32.to_le_bytes());
+    data_transfer.extend_from_slice(&0xAAAA_AAAAu32.to_le_bytes());
+    seeds.push(("data_transfer", data_transfer));
+
+    // msg_type 2: RPC call, payload is method_id + num_args, with
+    // num_args safely under the 16-argument cap.
+    let mut rpc_call = Vec::new();
+    rpc_call.extend_from_slice(&2u32.to_le_bytes());
+    rpc_call.extend_from_slice(&8u32.to_le_bytes());
+    rpc_call.extend_from_slice(&1u32.to_le_bytes()); // method_id
+    rpc_call.extend_from_slice(&2u32.to_le_bytes()); // num_args
+    seeds.push(("rpc_call", rpc_call));
+
+    seeds
+}
+
+/// A path containing both a `..` traversal component and an embedded NUL
+/// byte, the two patterns `fuzz_path_parser` specifically flags.
+fn path_traversal_seed() -> Vec<u8> {
+    b"../secret\0.txt".to_vec()
+}
+
+fn write_seed(dir: &Path, name: &str, bytes: &[u8]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(name), bytes)
+}
+
+fn generate(output_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    write_dictionary(&output_dir.join("neuro_tools.dict"))?;
+
+    let corpus_dir = output_dir.join("corpus");
+    write_seed(&corpus_dir.join("fuzz_syscall_dispatcher"), "valid_read", &valid_read_syscall_seed())?;
+    for (name, bytes) in ipc_message_seeds() {
+        write_seed(&corpus_dir.join("fuzz_ipc_message"), name, &bytes)?;
+    }
+    write_seed(&corpus_dir.join("fuzz_path_parser"), "dot_dot_and_null", &path_traversal_seed())?;
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let output_dir = match env::args().nth(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            eprintln!("usage: gen_corpus <output_dir>");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(err) = generate(&output_dir) {
+        eprintln!("gen_corpus failed: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_non_printable_bytes_as_hex() {
+        assert_eq!(escape_dict_value(&[0x00, 0xff]), "\"\\x00\\xFF\"");
+        assert_eq!(escape_dict_value(b"ok"), "\"ok\"");
+    }
+
+    #[test]
+    fn ipc_seeds_have_matching_payload_len() {
+        for (_name, blob) in ipc_message_seeds() {
+            let payload_len = u32::from_le_bytes(blob[4..8].try_into().unwrap()) as usize;
+            assert_eq!(blob.len() - 8, payload_len);
+        }
+    }
+
+    #[test]
+    fn path_seed_contains_dot_dot_and_null() {
+        let seed = path_traversal_seed();
+        let as_str = String::from_utf8_lossy(&seed);
+        assert!(as_str.contains(".."));
+        assert!(seed.contains(&0u8));
+    }
+}
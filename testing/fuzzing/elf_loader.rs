@@ -0,0 +1,364 @@
+// ELF Loader and Initial-Stack Fuzz Target
+//
+// The syscall surface fuzzed elsewhere in this crate stops at
+// `open`/`mmap`; none of it exercises program loading, which is where a
+// lot of the starnix-style loader's attack surface actually lives: header
+// parsing does arithmetic on attacker-controlled offsets and sizes, and
+// initial-stack setup writes argv/envp/auxv strings and pointers into a
+// fixed-size buffer. Both are exactly the kind of code an integer
+// overflow or an off-by-one turns into an OOB write. This target parses
+// an ELF64 header and program headers straight out of the fuzz input
+// (matching the real on-disk layout, not a streaming `FuzzConsumer`
+// encoding, since that's what a real loader reads), validates every
+// segment's bounds before trusting them, and then lays out a mock
+// initial stack the same way a loader hands control to `_start`.
+
+use crate::fuzz_consumer::FuzzConsumer;
+use std::hint::black_box;
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELF64_EHDR_SIZE: usize = 64;
+const ELF64_PHDR_SIZE: usize = 56;
+
+/// Upper bound on the virtual address space segments may be loaded into;
+/// standing in for the target process's VMO/address-space budget.
+const TARGET_VMO_SIZE: u64 = 0x1_0000_0000;
+
+/// Size of the mock stack buffer initial-stack population writes into.
+const STACK_SIZE: usize = 64 * 1024;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+struct Elf64Header {
+    phoff: u64,
+    phentsize: u16,
+    phnum: u16,
+}
+
+fn parse_elf_header(data: &[u8]) -> Option<Elf64Header> {
+    if data.len() < ELF64_EHDR_SIZE {
+        return None;
+    }
+    if data[0..4] != ELF_MAGIC || data[4] != ELFCLASS64 {
+        return None;
+    }
+
+    Some(Elf64Header {
+        phoff: read_u64(data, 32)?,
+        phentsize: read_u16(data, 54)?,
+        phnum: read_u16(data, 56)?,
+    })
+}
+
+struct Elf64ProgramHeader {
+    p_offset: u64,
+    p_vaddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+}
+
+fn parse_program_header(data: &[u8], offset: usize) -> Option<Elf64ProgramHeader> {
+    Some(Elf64ProgramHeader {
+        p_offset: read_u64(data, offset + 8)?,
+        p_vaddr: read_u64(data, offset + 16)?,
+        p_filesz: read_u64(data, offset + 32)?,
+        p_memsz: read_u64(data, offset + 40)?,
+    })
+}
+
+/// Parses every program header and validates it against the file and the
+/// target VMO before accepting it: `p_offset + p_filesz` must stay inside
+/// the file, `p_filesz` must never exceed `p_memsz` (the tail is
+/// zero-filled, not shrunk), `p_vaddr + p_memsz` must stay inside the VMO
+/// budget, and no two segments' virtual ranges may overlap. Every size
+/// computation goes through `checked_add`/`checked_mul` so a crafted
+/// offset near `u64::MAX` fails closed instead of wrapping into a
+/// bounds check that looks satisfied.
+fn validate_program_headers(data: &[u8], header: &Elf64Header) -> Option<Vec<Elf64ProgramHeader>> {
+    let phoff = usize::try_from(header.phoff).ok()?;
+    let phentsize = header.phentsize as usize;
+    if phentsize < ELF64_PHDR_SIZE {
+        return None;
+    }
+    let phnum = header.phnum as usize;
+
+    let mut headers = Vec::with_capacity(phnum);
+    let mut accepted_ranges: Vec<(u64, u64)> = Vec::new();
+
+    for i in 0..phnum {
+        let entry_offset = phoff.checked_add(i.checked_mul(phentsize)?)?;
+        if entry_offset.checked_add(ELF64_PHDR_SIZE)? > data.len() {
+            return None;
+        }
+        let ph = parse_program_header(data, entry_offset)?;
+
+        let file_end = ph.p_offset.checked_add(ph.p_filesz)?;
+        if file_end > data.len() as u64 {
+            return None;
+        }
+        if ph.p_filesz > ph.p_memsz {
+            return None;
+        }
+
+        let vaddr_end = ph.p_vaddr.checked_add(ph.p_memsz)?;
+        if vaddr_end > TARGET_VMO_SIZE {
+            return None;
+        }
+
+        for &(start, end) in &accepted_ranges {
+            if ph.p_vaddr < end && vaddr_end > start {
+                return None; // overlaps a previously accepted segment
+            }
+        }
+        accepted_ranges.push((ph.p_vaddr, vaddr_end));
+        headers.push(ph);
+    }
+
+    Some(headers)
+}
+
+/// A fixed-size stack buffer that grows down from the top, exactly as a
+/// real initial-process stack does, so an overflowing push fails the same
+/// way an overflowing `sp` decrement would.
+struct MockStack {
+    buf: [u8; STACK_SIZE],
+    sp: usize,
+}
+
+impl MockStack {
+    fn new() -> Self {
+        MockStack { buf: [0u8; STACK_SIZE], sp: STACK_SIZE }
+    }
+
+    /// Pushes `bytes` below the current stack pointer, returning the
+    /// offset they now live at, or `None` if they don't fit in the
+    /// remaining mock stack space rather than panicking.
+    fn push_bytes(&mut self, bytes: &[u8]) -> Option<usize> {
+        let new_sp = self.sp.checked_sub(bytes.len())?;
+        self.buf[new_sp..self.sp].copy_from_slice(bytes);
+        self.sp = new_sp;
+        Some(self.sp)
+    }
+
+    fn push_u64(&mut self, value: u64) -> Option<usize> {
+        self.push_bytes(&value.to_le_bytes())
+    }
+
+    /// Aligns `sp` down to `align` (a power of two), as a loader does
+    /// before it starts laying out the pointer arrays.
+    fn align_down(&mut self, align: usize) {
+        self.sp &= !(align - 1);
+    }
+}
+
+/// Lays argv strings, envp strings, a 16-byte `AT_RANDOM` seed, and the
+/// auxv key/value pairs onto `stack`, in the same order and direction a
+/// real loader writes them (strings first, highest addresses; then the
+/// argc/argv/envp/auxv pointer arrays, lowest addresses, ending at the
+/// final `sp` handed to `_start`). Returns `None` if anything overflows
+/// the mock stack; the final `sp` is always 16-byte aligned, as the ABI
+/// requires.
+fn populate_initial_stack(
+    stack: &mut MockStack,
+    argv: &[&[u8]],
+    envp: &[&[u8]],
+    auxv: &[(u64, u64)],
+) -> Option<usize> {
+    let random_seed = [0u8; 16];
+    stack.push_bytes(&random_seed)?;
+
+    let mut argv_addrs = Vec::with_capacity(argv.len());
+    for arg in argv.iter().rev() {
+        let mut bytes = arg.to_vec();
+        bytes.push(0); // NUL terminator
+        argv_addrs.push(stack.push_bytes(&bytes)? as u64);
+    }
+    argv_addrs.reverse();
+
+    let mut envp_addrs = Vec::with_capacity(envp.len());
+    for var in envp.iter().rev() {
+        let mut bytes = var.to_vec();
+        bytes.push(0);
+        envp_addrs.push(stack.push_bytes(&bytes)? as u64);
+    }
+    envp_addrs.reverse();
+
+    let random_seed = [0u8; 16];
+    stack.push_bytes(&random_seed)?;
+
+    // Everything above this point was string data; the pointer arrays
+    // below must start word-aligned.
+    stack.align_down(8);
+
+    // Pad once here, above the pointer arrays, so that argc lands on a
+    // 16-aligned `sp` directly below argv.
+    let words = 2 + 2 * auxv.len() + 1 + envp.len() + 1 + argv.len() + 1;
+    if (stack.sp / 8 + words) % 2 != 0 {
+        stack.push_u64(0)?; // alignment padding
+    }
+
+    stack.push_u64(0)?; // AT_NULL
+    stack.push_u64(0)?;
+    for &(key, value) in auxv.iter().rev() {
+        stack.push_u64(value)?;
+        stack.push_u64(key)?;
+    }
+
+    stack.push_u64(0)?; // envp NULL terminator
+    for &addr in envp_addrs.iter().rev() {
+        stack.push_u64(addr)?;
+    }
+
+    stack.push_u64(0)?; // argv NULL terminator
+    for &addr in argv_addrs.iter().rev() {
+        stack.push_u64(addr)?;
+    }
+
+    stack.push_u64(argv.len() as u64)?; // argc
+
+    Some(stack.sp)
+}
+
+/// Fuzz target for ELF program loading.
+///
+/// Input format: an ELF64 header and program header table at their real
+/// file offsets, as `fuzz_elf_loader` would actually read them off disk,
+/// followed by a `FuzzConsumer`-encoded argc/argv/envc/envp/auxv stream
+/// used to synthesize the initial stack.
+pub fn fuzz_elf_loader(data: &[u8]) {
+    let Some(header) = parse_elf_header(data) else { return };
+    if validate_program_headers(data, &header).is_none() {
+        return;
+    }
+
+    // The stack stream starts after the program header table, so header
+    // bytes are never re-read as argv/envp/auxv fields.
+    let phdr_table_end =
+        (header.phoff as usize).saturating_add(header.phnum as usize * header.phentsize as usize);
+    let mut consumer = FuzzConsumer::new(data);
+    consumer.consume_bytes(phdr_table_end.max(ELF64_EHDR_SIZE));
+
+    let argc = consumer.consume_int_in_range(0, 8) as usize;
+    let mut argv_storage = Vec::with_capacity(argc);
+    for _ in 0..argc {
+        let len = consumer.consume_int_in_range(0, 32) as usize;
+        argv_storage.push(consumer.consume_bytes(len).to_vec());
+    }
+
+    let envc = consumer.consume_int_in_range(0, 8) as usize;
+    let mut envp_storage = Vec::with_capacity(envc);
+    for _ in 0..envc {
+        let len = consumer.consume_int_in_range(0, 32) as usize;
+        envp_storage.push(consumer.consume_bytes(len).to_vec());
+    }
+
+    let auxc = consumer.consume_int_in_range(0, 16) as usize;
+    let mut auxv = Vec::with_capacity(auxc);
+    for _ in 0..auxc {
+        auxv.push((consumer.consume_u64(), consumer.consume_u64()));
+    }
+
+    let argv: Vec<&[u8]> = argv_storage.iter().map(Vec::as_slice).collect();
+    let envp: Vec<&[u8]> = envp_storage.iter().map(Vec::as_slice).collect();
+
+    let mut stack = MockStack::new();
+    if let Some(final_sp) = populate_initial_stack(&mut stack, &argv, &envp, &auxv) {
+        black_box(final_sp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimal_elf_header() -> Vec<u8> {
+        let mut data = vec![0u8; ELF64_EHDR_SIZE];
+        data[0..4].copy_from_slice(&ELF_MAGIC);
+        data[4] = ELFCLASS64;
+        data[54..56].copy_from_slice(&(ELF64_PHDR_SIZE as u16).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut data = minimal_elf_header();
+        data[0] = 0;
+        assert!(parse_elf_header(&data).is_none());
+    }
+
+    #[test]
+    fn rejects_program_header_overflowing_file_bounds() {
+        let mut data = minimal_elf_header();
+        data[32..40].copy_from_slice(&(ELF64_EHDR_SIZE as u64).to_le_bytes()); // phoff
+        data[56..58].copy_from_slice(&1u16.to_le_bytes()); // phnum = 1
+        data.extend(vec![0u8; ELF64_PHDR_SIZE]);
+
+        let phdr_offset = ELF64_EHDR_SIZE;
+        data[phdr_offset + 8..phdr_offset + 16].copy_from_slice(&0u64.to_le_bytes()); // p_offset
+        data[phdr_offset + 32..phdr_offset + 40].copy_from_slice(&u64::MAX.to_le_bytes()); // p_filesz
+
+        let header = parse_elf_header(&data).unwrap();
+        assert!(validate_program_headers(&data, &header).is_none());
+    }
+
+    #[test]
+    fn rejects_overlapping_load_segments() {
+        let mut data = minimal_elf_header();
+        let phoff = ELF64_EHDR_SIZE;
+        data[32..40].copy_from_slice(&(phoff as u64).to_le_bytes());
+        data[56..58].copy_from_slice(&2u16.to_le_bytes()); // phnum = 2
+        data.extend(vec![0u8; ELF64_PHDR_SIZE * 2]);
+
+        for (i, vaddr) in [0u64, 0x1000].into_iter().enumerate() {
+            let entry = phoff + i * ELF64_PHDR_SIZE;
+            data[entry + 16..entry + 24].copy_from_slice(&vaddr.to_le_bytes());
+            data[entry + 40..entry + 48].copy_from_slice(&0x2000u64.to_le_bytes()); // p_memsz
+        }
+
+        let header = parse_elf_header(&data).unwrap();
+        assert!(validate_program_headers(&data, &header).is_none());
+    }
+
+    #[test]
+    fn populates_a_well_formed_stack() {
+        let mut stack = MockStack::new();
+        let argv: Vec<&[u8]> = vec![b"init"];
+        let envp: Vec<&[u8]> = vec![b"HOME=/"];
+        let auxv = [(3u64, 0x1000u64)];
+
+        let sp = populate_initial_stack(&mut stack, &argv, &envp, &auxv).unwrap();
+        assert_eq!(sp % 16, 0);
+        assert!(sp < STACK_SIZE);
+    }
+
+    #[test]
+    fn final_sp_is_always_16_byte_aligned() {
+        // Vary the argv/envp/auxv counts so both parities of pushes before
+        // the alignment padding decision get exercised.
+        for argc in 0..4 {
+            for envc in 0..4 {
+                let mut stack = MockStack::new();
+                let argv: Vec<&[u8]> = vec![b"a"; argc];
+                let envp: Vec<&[u8]> = vec![b"E=1"; envc];
+                let auxv = [(3u64, 0x1000u64)];
+                let sp = populate_initial_stack(&mut stack, &argv, &envp, &auxv).unwrap();
+                assert_eq!(sp % 16, 0, "argc={argc} envc={envc}");
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_argv_fails_closed_without_panicking() {
+        let mut stack = MockStack::new();
+        let huge = vec![0u8; STACK_SIZE * 2];
+        let argv: Vec<&[u8]> = vec![&huge];
+        assert!(populate_initial_stack(&mut stack, &argv, &[], &[]).is_none());
+    }
+}
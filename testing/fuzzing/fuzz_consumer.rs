@@ -0,0 +1,119 @@
+// FuzzedDataProvider-Style Structured Input Consumer
+//
+// Every target in this crate used to hand-roll byte slicing with
+// `try_into().unwrap()` and bail out with an early `return` whenever the
+// buffer ran short, which wastes most inputs the fuzzer generates: a
+// single short input rejects the whole test case instead of still
+// exercising whatever fields it *does* have room for. `FuzzConsumer` wraps
+// a byte slice the way libFuzzer's C++ `FuzzedDataProvider` does: every
+// `consume_*` call advances a cursor and returns a sensible default once
+// the data is exhausted, instead of panicking or aborting.
+
+/// A cursor over fuzzer input that hands out structured values field by
+/// field, never panicking even once the underlying bytes run out.
+pub struct FuzzConsumer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> FuzzConsumer<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        FuzzConsumer { data, pos: 0 }
+    }
+
+    /// Bytes not yet consumed.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Consumes up to `n` bytes, returning fewer (down to none) if the
+    /// input is exhausted rather than panicking.
+    pub fn consume_bytes(&mut self, n: usize) -> &'a [u8] {
+        let take = n.min(self.remaining());
+        let slice = &self.data[self.pos..self.pos + take];
+        self.pos += take;
+        slice
+    }
+
+    /// Consumes 4 bytes as a little-endian `u32`, zero-padding on
+    /// exhaustion instead of failing.
+    pub fn consume_u32(&mut self) -> u32 {
+        let bytes = self.consume_bytes(4);
+        let mut buf = [0u8; 4];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        u32::from_le_bytes(buf)
+    }
+
+    /// Consumes 8 bytes as a little-endian `u64`, zero-padding on
+    /// exhaustion instead of failing.
+    pub fn consume_u64(&mut self) -> u64 {
+        let bytes = self.consume_bytes(8);
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(buf)
+    }
+
+    /// Consumes an integer in `[lo, hi]` (inclusive) via modular reduction
+    /// of a consumed `u64`, so the mutator can reach any value in range
+    /// directly instead of the caller doing its own `% n` on a raw field
+    /// (and losing the ability to target specific values by construction).
+    pub fn consume_int_in_range(&mut self, lo: u64, hi: u64) -> u64 {
+        if hi <= lo {
+            return lo;
+        }
+        // `hi - lo + 1` overflows to 0 when `hi == u64::MAX`; treat that as
+        // "the full u64 range" instead of panicking/dividing by zero.
+        let span = (hi - lo).wrapping_add(1);
+        if span == 0 {
+            return self.consume_u64();
+        }
+        lo + (self.consume_u64() % span)
+    }
+
+    /// Consumes a selector in `[0, variant_count)`, for picking one of a
+    /// fixed set of enum-like cases.
+    pub fn consume_enum(&mut self, variant_count: usize) -> usize {
+        if variant_count == 0 {
+            return 0;
+        }
+        self.consume_int_in_range(0, variant_count as u64 - 1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consumes_fields_in_order() {
+        let data = [1u8, 0, 0, 0, 2, 0, 0, 0];
+        let mut consumer = FuzzConsumer::new(&data);
+        assert_eq!(consumer.consume_u32(), 1);
+        assert_eq!(consumer.consume_u32(), 2);
+        assert_eq!(consumer.remaining(), 0);
+    }
+
+    #[test]
+    fn exhausted_input_yields_defaults_not_panics() {
+        let data = [0xFFu8];
+        let mut consumer = FuzzConsumer::new(&data);
+        let _ = consumer.consume_u64();
+        assert_eq!(consumer.consume_u64(), 0);
+        assert_eq!(consumer.consume_enum(5), 0);
+    }
+
+    #[test]
+    fn int_in_range_stays_in_bounds() {
+        let data = [0xFFu8; 8];
+        let mut consumer = FuzzConsumer::new(&data);
+        let value = consumer.consume_int_in_range(10, 20);
+        assert!((10..=20).contains(&value));
+    }
+
+    #[test]
+    fn int_in_range_with_full_span_does_not_panic() {
+        let data = [0x42u8; 8];
+        let mut consumer = FuzzConsumer::new(&data);
+        let _ = consumer.consume_int_in_range(0, u64::MAX);
+    }
+}
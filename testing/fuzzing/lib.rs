@@ -17,6 +17,14 @@
 
 #![no_main]
 
+mod elf_loader;
+mod fuzz_consumer;
+mod fuzz_env;
+mod persistent_allocator;
+mod timing_fuzz;
+
+use fuzz_consumer::FuzzConsumer;
+use fuzz_env::{EnvMode, FuzzEnv};
 use libfuzzer_sys::fuzz_target;
 use std::hint::black_box;
 
@@ -28,110 +36,130 @@ use std::hint::black_box;
 ///
 /// Input format: [syscall_number: u64][arg1: u64][arg2: u64]...[arg6: u64]
 fuzz_target!(|data: &[u8]| {
-    // Ensure we have enough data for at least the syscall number
-    if data.len() < 8 {
-        return;
-    }
-    
-    // Parse syscall number
-    let syscall_num = u64::from_le_bytes(data[0..8].try_into().unwrap());
-    
-    // Parse up to 6 arguments (standard x86-64 syscall convention)
+    let mut consumer = FuzzConsumer::new(data);
+
+    let syscall_num = consumer.consume_u64();
     let mut args = [0u64; 6];
-    let mut offset = 8;
-    
-    for i in 0..6 {
-        if offset + 8 <= data.len() {
-            args[i] = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
-            offset += 8;
-        }
+    for arg in &mut args {
+        *arg = consumer.consume_u64();
     }
-    
+
+    // Whatever bytes are left back the deterministic environment shim, so
+    // any "current time"/"random"/"fd table" the dispatch path consults
+    // comes from the input rather than the live system and a crashing
+    // input replays identically every time.
+    let tail = consumer.consume_bytes(consumer.remaining());
+    let mut env = FuzzEnv::new(tail, EnvMode::DeterministicReplay);
+
     // Fuzz the syscall dispatcher (mock implementation)
     // In real code, this would call into the actual syscall handler
-    fuzz_syscall_dispatcher(syscall_num, &args);
+    fuzz_syscall_dispatcher(syscall_num, &args, &mut env);
 });
 
 /// Mock syscall dispatcher for fuzzing.
 ///
 /// This simulates the kernel's syscall dispatch logic without actually
 /// executing privileged operations.
-fn fuzz_syscall_dispatcher(syscall_num: u64, args: &[u64; 6]) {
+///
+/// `pub(crate)` so `timing_fuzz` can drive it directly with
+/// attacker-chosen arguments instead of going through the `fuzz_target!`
+/// entry point, which is only callable by libFuzzer itself.
+pub(crate) fn fuzz_syscall_dispatcher(syscall_num: u64, args: &[u64; 6], env: &mut FuzzEnv) {
     match syscall_num {
         // read(fd, buf, count)
         0 => {
             let fd = args[0] as i32;
             let buf_addr = args[1];
             let count = args[2] as usize;
-            
+
             // Validate file descriptor
             if fd < 0 {
                 return;
             }
-            
+
+            // Consult the (fuzzer-controlled) current fd table instead of
+            // treating every non-negative fd as open.
+            if !fd_is_open(env, fd) {
+                return;
+            }
+
             // Validate buffer address (must be in user space)
             if buf_addr >= 0x0000_8000_0000_0000 {
                 return; // Invalid user space address
             }
-            
+
             // Validate count
             if count > 0x7FFFF000 {
                 return; // Too large
             }
-            
+
             black_box((fd, buf_addr, count));
         }
-        
+
         // write(fd, buf, count)
         1 => {
             let fd = args[0] as i32;
             let buf_addr = args[1];
             let count = args[2] as usize;
-            
+
             // Similar validation as read
-            if fd < 0 || buf_addr >= 0x0000_8000_0000_0000 || count > 0x7FFFF000 {
+            if fd < 0 || !fd_is_open(env, fd) || buf_addr >= 0x0000_8000_0000_0000 || count > 0x7FFFF000 {
                 return;
             }
-            
+
             black_box((fd, buf_addr, count));
         }
-        
+
         // open(path, flags, mode)
         2 => {
             let path_addr = args[0];
             let flags = args[1] as i32;
             let mode = args[2] as u32;
-            
+
             // Validate path address
             if path_addr == 0 || path_addr >= 0x0000_8000_0000_0000 {
                 return;
             }
-            
-            black_box((path_addr, flags, mode));
+
+            // A real open() allocates the lowest free fd from the current
+            // fd table; stand that in with the deterministic environment
+            // shim rather than the live process state.
+            let assigned_fd = (!env.current_fd_table()).trailing_zeros();
+
+            black_box((path_addr, flags, mode, assigned_fd));
         }
-        
+
         // mmap(addr, length, prot, flags, fd, offset)
         9 => {
-            let addr = args[0];
+            let requested_addr = args[0];
             let length = args[1] as usize;
             let prot = args[2] as i32;
             let flags = args[3] as i32;
             let fd = args[4] as i32;
             let offset = args[5] as i64;
-            
+
             // Validate length
             if length == 0 || length > 0x7FFFF000 {
                 return;
             }
-            
+
             // Validate address alignment
-            if addr != 0 && (addr & 0xFFF) != 0 {
+            if requested_addr != 0 && (requested_addr & 0xFFF) != 0 {
                 return;
             }
-            
+
+            // addr == 0 means "let the kernel choose", which would
+            // normally consult ASLR randomness; go through the
+            // deterministic shim so a crash at a given placement replays.
+            let addr = if requested_addr == 0 {
+                page_align(env.getrandom() & 0x0000_7FFF_FFFF_FFFF)
+            } else {
+                requested_addr
+            };
+
             black_box((addr, length, prot, flags, fd, offset));
         }
-        
+
         // Default: unknown syscall
         _ => {
             black_box(syscall_num);
@@ -139,6 +167,17 @@ fn fuzz_syscall_dispatcher(syscall_num: u64, args: &[u64; 6]) {
     }
 }
 
+/// Consults the deterministic fd-table shim to decide whether `fd` is
+/// currently open, rather than assuming every non-negative fd is valid.
+fn fd_is_open(env: &mut FuzzEnv, fd: i32) -> bool {
+    let table = env.current_fd_table();
+    (0..32).contains(&fd) && (table >> fd) & 1 == 1
+}
+
+fn page_align(addr: u64) -> u64 {
+    addr & !0xFFF
+}
+
 /// Fuzz target for memory allocator.
 ///
 /// Memory allocators are complex and must handle arbitrary allocation
@@ -146,75 +185,52 @@ fn fuzz_syscall_dispatcher(syscall_num: u64, args: &[u64; 6]) {
 /// and reallocation operations.
 ///
 /// Input format: [operation: u8][size: u32][align: u32]...
-#[export_name = "LLVMFuzzerCustomMutator"]
 pub fn fuzz_allocator(data: &[u8]) {
-    if data.is_empty() {
-        return;
-    }
-    
-    let mut offset = 0;
+    let mut consumer = FuzzConsumer::new(data);
     let mut allocations: Vec<(*mut u8, usize)> = Vec::new();
-    
-    while offset < data.len() {
-        if offset + 9 > data.len() {
-            break;
-        }
-        
-        let operation = data[offset];
-        let size = u32::from_le_bytes(data[offset + 1..offset + 5].try_into().unwrap()) as usize;
-        let align = u32::from_le_bytes(data[offset + 5..offset + 9].try_into().unwrap()) as usize;
-        offset += 9;
-        
-        match operation % 3 {
+
+    while consumer.remaining() > 0 {
+        match consumer.consume_enum(3) {
             // Allocate
             0 => {
-                // Clamp size to reasonable values
-                let size = size % 0x100000; // Max 1MB
-                if size == 0 {
-                    continue;
-                }
-                
-                // Ensure alignment is power of 2
-                let align = if align == 0 || !align.is_power_of_two() {
-                    8
-                } else {
-                    align.min(4096)
-                };
-                
+                let size = consumer.consume_int_in_range(1, 0x100000) as usize; // 1 byte .. 1MB
+                // Ensure alignment is a power of 2 by picking its shift directly,
+                // rather than generating an arbitrary value and rejecting it.
+                let align_shift = consumer.consume_int_in_range(3, 12); // 8 .. 4096
+                let align = 1usize << align_shift;
+
                 // Simulate allocation (don't actually allocate in fuzzer)
                 let fake_ptr = (size | align) as *mut u8;
                 allocations.push((fake_ptr, size));
                 black_box((size, align));
             }
-            
+
             // Deallocate
             1 => {
                 if !allocations.is_empty() {
-                    let idx = (size as usize) % allocations.len();
+                    let idx = consumer.consume_int_in_range(0, allocations.len() as u64 - 1) as usize;
                     let (ptr, size) = allocations.remove(idx);
                     black_box((ptr, size));
                 }
             }
-            
+
             // Reallocate
             2 => {
                 if !allocations.is_empty() {
-                    let idx = (size as usize) % allocations.len();
+                    let idx = consumer.consume_int_in_range(0, allocations.len() as u64 - 1) as usize;
                     let (old_ptr, old_size) = allocations[idx];
-                    let new_size = (align as usize) % 0x100000;
-                    
-                    if new_size > 0 {
-                        let fake_new_ptr = (new_size | old_size) as *mut u8;
-                        allocations[idx] = (fake_new_ptr, new_size);
-                        black_box((old_ptr, old_size, new_size));
-                    }
+                    let new_size = consumer.consume_int_in_range(1, 0x100000) as usize;
+
+                    let fake_new_ptr = (new_size | old_size) as *mut u8;
+                    allocations[idx] = (fake_new_ptr, new_size);
+                    black_box((old_ptr, old_size, new_size));
                 }
             }
-            
+
             _ => unreachable!(),
         }
     }
-    
+
     // Clean up all allocations
     for (ptr, size) in allocations {
         black_box((ptr, size));
@@ -229,25 +245,25 @@ pub fn fuzz_allocator(data: &[u8]) {
 /// Input format: [msg_type: u32][payload_len: u32][payload: ...]
 #[export_name = "LLVMFuzzerTestOneInput"]
 pub fn fuzz_ipc_message(data: &[u8]) {
-    if data.len() < 8 {
-        return;
-    }
-    
-    // Parse message header
-    let msg_type = u32::from_le_bytes(data[0..4].try_into().unwrap());
-    let payload_len = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
-    
-    // Validate payload length
-    if payload_len > data.len() - 8 {
+    let mut consumer = FuzzConsumer::new(data);
+
+    let msg_type = consumer.consume_u32();
+    let payload_len = consumer.consume_u32() as usize;
+    let payload = consumer.consume_bytes(payload_len);
+
+    // The consumer hands back fewer bytes than requested once the input is
+    // exhausted rather than panicking, so a too-small payload just reads as
+    // truncated here instead of indexing out of bounds.
+    if payload.len() < payload_len {
         return; // Truncated message
     }
-    
+
     if payload_len > 0x100000 {
         return; // Too large
     }
-    
-    let payload = &data[8..8 + payload_len];
-    
+
+    let mut payload_consumer = FuzzConsumer::new(payload);
+
     // Parse message based on type
     match msg_type {
         // Simple notification
@@ -256,30 +272,30 @@ pub fn fuzz_ipc_message(data: &[u8]) {
                 black_box(msg_type);
             }
         }
-        
+
         // Data transfer
         1 => {
             if payload.len() >= 4 {
-                let data_id = u32::from_le_bytes(payload[0..4].try_into().unwrap());
-                black_box((msg_type, data_id, &payload[4..]));
+                let data_id = payload_consumer.consume_u32();
+                black_box((msg_type, data_id, payload_consumer.consume_bytes(payload_consumer.remaining())));
             }
         }
-        
+
         // RPC call
         2 => {
             if payload.len() >= 8 {
-                let method_id = u32::from_le_bytes(payload[0..4].try_into().unwrap());
-                let num_args = u32::from_le_bytes(payload[4..8].try_into().unwrap());
-                
+                let method_id = payload_consumer.consume_u32();
+                let num_args = payload_consumer.consume_u32();
+
                 // Validate number of arguments
                 if num_args > 16 {
                     return;
                 }
-                
+
                 black_box((msg_type, method_id, num_args));
             }
         }
-        
+
         // Unknown message type - should be handled gracefully
         _ => {
             black_box((msg_type, payload));
@@ -292,8 +308,11 @@ pub fn fuzz_ipc_message(data: &[u8]) {
 /// Path parsing is security-critical as it can lead to directory traversal
 /// vulnerabilities if not handled correctly.
 fn fuzz_path_parser(data: &[u8]) {
+    let mut consumer = FuzzConsumer::new(data);
+    let path_bytes = consumer.consume_bytes(consumer.remaining());
+
     // Try to parse as UTF-8 path
-    if let Ok(path_str) = std::str::from_utf8(data) {
+    if let Ok(path_str) = std::str::from_utf8(path_bytes) {
         // Check for various path traversal patterns
         let has_dot_dot = path_str.contains("..");
         let has_absolute = path_str.starts_with('/');
@@ -319,3 +338,149 @@ fn fuzz_path_parser(data: &[u8]) {
         black_box((has_dot_dot, has_absolute, has_null_byte, components));
     }
 }
+
+// Structure-Aware Custom Mutator
+//
+// libFuzzer calls into `LLVMFuzzerCustomMutator` (when exported) instead of
+// its built-in byte-flipping mutator. The syscall and IPC targets reject
+// most randomly-flipped inputs in their first few validation checks, so a
+// structure-blind mutator spends almost all its budget re-discovering the
+// same "too short" / "length mismatch" rejections. This mutator understands
+// both targets' fixed header layouts and mutates a whole field at a time:
+// either snapping it to a boundary value the validators are known to branch
+// on, or (for the IPC layout) fixing up `payload_len` so the message stops
+// being rejected as truncated.
+
+extern "C" {
+    fn LLVMFuzzerMutate(data: *mut u8, size: usize, max_size: usize) -> usize;
+}
+
+/// Boundary values the `fuzz_syscall_dispatcher` validation branches check
+/// against directly: the `count`/`length` cap, the user/kernel address
+/// split, the page-align mask, and the all-zero/all-ones extremes.
+const SYSCALL_BOUNDARY_VALUES: [u64; 5] = [0, u64::MAX, 0xFFF, 0x7FFFF000, 0x0000_8000_0000_0000];
+
+/// The only syscall numbers `fuzz_syscall_dispatcher` dispatches on a named
+/// path for; everything else falls into its default arm.
+const VALID_SYSCALL_NUMBERS: [u64; 4] = [0, 1, 2, 9];
+
+/// Boundary values `fuzz_ipc_message`'s `payload_len` check branches on.
+const IPC_PAYLOAD_LEN_BOUNDARY_VALUES: [u32; 4] = [0, u32::MAX, 0x100000, 16];
+
+/// A small seeded xorshift64* generator. libFuzzer hands us a `u32` seed
+/// expecting the mutation to be reproducible from it; pulling in a full RNG
+/// crate for that would be overkill next to `fuzz_allocator`'s existing
+/// hand-rolled byte parsing.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        // Avoid an all-zero state, which xorshift can't escape.
+        Rng(((seed as u64) << 1 | 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn gen_range(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            0
+        } else {
+            (self.next_u64() % bound as u64) as usize
+        }
+    }
+}
+
+/// Mutates one field of the `[syscall_num: u64][arg1..arg6: u64]` layout in
+/// place. Returns `false` if `buf` is too short to contain a full field.
+fn mutate_syscall_layout(buf: &mut [u8], rng: &mut Rng) -> bool {
+    if buf.len() < 8 {
+        return false;
+    }
+
+    let num_fields = buf.len() / 8;
+    let field = rng.gen_range(num_fields);
+    let offset = field * 8;
+
+    if field == 0 && rng.gen_range(2) == 0 {
+        let value = VALID_SYSCALL_NUMBERS[rng.gen_range(VALID_SYSCALL_NUMBERS.len())];
+        buf[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    } else if rng.gen_range(3) != 0 {
+        let value = SYSCALL_BOUNDARY_VALUES[rng.gen_range(SYSCALL_BOUNDARY_VALUES.len())];
+        buf[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    } else {
+        unsafe {
+            let field_ptr = buf.as_mut_ptr().add(offset);
+            LLVMFuzzerMutate(field_ptr, 8, 8);
+        }
+    }
+
+    true
+}
+
+/// Mutates the `[msg_type: u32][payload_len: u32]` header of the IPC
+/// layout in place. Returns `false` if `buf` is too short to contain it.
+fn mutate_ipc_layout(buf: &mut [u8], rng: &mut Rng) -> bool {
+    if buf.len() < 8 {
+        return false;
+    }
+
+    match rng.gen_range(3) {
+        0 => {
+            // Fix payload_len to the actual remaining buffer so the
+            // message stops being rejected as truncated.
+            let remaining = (buf.len() - 8) as u32;
+            buf[4..8].copy_from_slice(&remaining.to_le_bytes());
+        }
+        1 => {
+            let msg_type = [0u32, 1, 2][rng.gen_range(3)];
+            buf[0..4].copy_from_slice(&msg_type.to_le_bytes());
+        }
+        _ => {
+            let value = IPC_PAYLOAD_LEN_BOUNDARY_VALUES[rng.gen_range(IPC_PAYLOAD_LEN_BOUNDARY_VALUES.len())];
+            buf[4..8].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    true
+}
+
+/// libFuzzer custom mutator entry point: `(data, size, max_size, seed) ->
+/// new_size`. The structured paths never grow the input past `size` (both
+/// targets' headers are fixed-size and present at offset 0, so there's no
+/// benefit to resizing there), they only rewrite field contents in place.
+/// Only the fallback to `LLVMFuzzerMutate` for a buffer too short for
+/// either layout may resize it, up to `max_size`.
+#[no_mangle]
+pub extern "C" fn LLVMFuzzerCustomMutator(data: *mut u8, size: usize, max_size: usize, seed: u32) -> usize {
+    if data.is_null() || size == 0 {
+        return size;
+    }
+
+    let mut rng = Rng::new(seed);
+    let buf = unsafe { std::slice::from_raw_parts_mut(data, size) };
+
+    // Neither target is identifiable from the bytes alone, so split
+    // attempts evenly between the two known layouts; if the buffer is too
+    // short for either, fall back to libFuzzer's own bit/byte mutation over
+    // the whole buffer.
+    let mutated = if rng.gen_range(2) == 0 {
+        mutate_syscall_layout(buf, &mut rng)
+    } else {
+        mutate_ipc_layout(buf, &mut rng)
+    };
+
+    if !mutated {
+        unsafe {
+            return LLVMFuzzerMutate(data, size, max_size);
+        }
+    }
+
+    size
+}
@@ -0,0 +1,281 @@
+// Persistent Allocator Fuzz Harness
+//
+// `fuzz_allocator` rebuilds its `allocations` vector from scratch on every
+// call and picks targets with `% allocations.len()`, so a double-free or a
+// realloc of a dead pointer just wraps around onto some other live entry
+// instead of being caught — and no bug that only manifests after
+// thousands of alloc/free/realloc calls can ever be reached, since state
+// never survives past one input. This harness instead keeps one
+// `PersistentAllocator` and the full history of ids it has ever issued
+// alive for the life of the fuzzer process (the "persistent fuzzing"
+// pattern: fuzz a wider loop instead of re-initializing global state every
+// case), and turns a stale-id free/realloc into an immediate panic rather
+// than a silently remapped operation.
+//
+// As with `ReferenceAllocator` in the property tests, `PersistentAllocator`
+// also stands in for the system-under-test, since this crate has no real
+// allocator to link against yet.
+
+use crate::fuzz_consumer::FuzzConsumer;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+pub type AllocId = u64;
+
+/// Whether an id is still backed by a live region or has already been
+/// freed. Kept distinct from "never issued" (a missing table entry), so
+/// `free`/`realloc` can tell a double-free from a bogus id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Liveness {
+    Live { offset: usize, size: usize },
+    Freed,
+}
+
+/// A first-fit simulated allocator over a fixed-size arena, mirroring
+/// `ReferenceAllocator`'s model in `property_tests.rs` but addressed by a
+/// persistent `AllocId` instead of a vector index, so ids keep meaning the
+/// same allocation across calls and across a snapshot/restore.
+pub struct PersistentAllocator {
+    arena_size: usize,
+    /// Live regions, sorted by offset, used for first-fit gap search.
+    live_regions: Vec<(usize, usize)>,
+    table: HashMap<AllocId, Liveness>,
+    next_id: AllocId,
+}
+
+impl PersistentAllocator {
+    pub fn new(arena_size: usize) -> Self {
+        PersistentAllocator {
+            arena_size,
+            live_regions: Vec::new(),
+            table: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn align_up(value: usize, align: usize) -> usize {
+        (value + align - 1) & !(align - 1)
+    }
+
+    /// Finds the first gap (including the tail of the arena) big enough
+    /// for `size` bytes aligned to `align`, returning the freshly issued
+    /// id or `None` if the arena is full.
+    pub fn alloc(&mut self, size: usize, align: usize) -> Option<AllocId> {
+        let mut cursor = 0usize;
+        for &(start, len) in &self.live_regions {
+            let aligned = Self::align_up(cursor, align);
+            if aligned + size <= start {
+                return Some(self.insert(aligned, size));
+            }
+            cursor = start + len;
+        }
+
+        let aligned = Self::align_up(cursor, align);
+        if aligned + size <= self.arena_size {
+            Some(self.insert(aligned, size))
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, offset: usize, size: usize) -> AllocId {
+        let pos = self.live_regions.partition_point(|&(start, _)| start < offset);
+        self.live_regions.insert(pos, (offset, size));
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.table.insert(id, Liveness::Live { offset, size });
+        id
+    }
+
+    /// Frees `id`. Panics — a detectable abort a fuzzer can catch, rather
+    /// than the old `% allocations.len()` scheme silently remapping to
+    /// some other live allocation — if `id` was never issued or is
+    /// already freed.
+    pub fn free(&mut self, id: AllocId) {
+        match self.table.get(&id) {
+            None => panic!("free of never-allocated id {id}"),
+            Some(Liveness::Freed) => panic!("double free of id {id}"),
+            Some(Liveness::Live { offset, size }) => {
+                let (offset, size) = (*offset, *size);
+                self.live_regions.retain(|&(start, len)| (start, len) != (offset, size));
+                self.table.insert(id, Liveness::Freed);
+            }
+        }
+    }
+
+    /// Reallocates `id` to `new_size`, freeing the old region and
+    /// allocating a fresh one. Panics under the same conditions as
+    /// `free`: a dead or unknown `id` means a use-after-free, not a
+    /// legitimate resize.
+    pub fn realloc(&mut self, id: AllocId, new_size: usize, align: usize) -> Option<AllocId> {
+        match self.table.get(&id) {
+            None => panic!("realloc of never-allocated id {id}"),
+            Some(Liveness::Freed) => panic!("realloc of dead id {id}"),
+            Some(Liveness::Live { .. }) => {}
+        }
+        self.free(id);
+        self.alloc(new_size, align)
+    }
+
+    /// Captures the full allocator state so a corpus minimizer can rewind
+    /// to this point between sub-sequences without losing earlier op
+    /// history (the ids already issued still exist for later ops to
+    /// reference).
+    pub fn snapshot(&self) -> AllocatorSnapshot {
+        AllocatorSnapshot {
+            live_regions: self.live_regions.clone(),
+            table: self.table.clone(),
+            next_id: self.next_id,
+        }
+    }
+
+    /// Rewinds to a previously captured `snapshot`.
+    pub fn restore(&mut self, snapshot: &AllocatorSnapshot) {
+        self.live_regions = snapshot.live_regions.clone();
+        self.table = snapshot.table.clone();
+        self.next_id = snapshot.next_id;
+    }
+}
+
+/// A point-in-time copy of `PersistentAllocator` state produced by
+/// `snapshot` and later handed back to `restore`.
+#[derive(Clone)]
+pub struct AllocatorSnapshot {
+    live_regions: Vec<(usize, usize)>,
+    table: HashMap<AllocId, Liveness>,
+    next_id: AllocId,
+}
+
+/// Arena size backing the persistent allocator: comfortably above the
+/// single-allocation cap (`fuzz_allocator`'s 1MB) so most op streams don't
+/// spuriously exhaust it.
+const ARENA_SIZE: usize = 16 * 1024 * 1024;
+
+struct PersistentState {
+    allocator: PersistentAllocator,
+    /// Every id ever issued, live or freed, so the op stream can
+    /// deliberately pick a stale one and exercise the double-free /
+    /// realloc-dead-id abort paths instead of only ever touching fresh
+    /// allocations.
+    known_ids: Vec<AllocId>,
+}
+
+static STATE: OnceLock<Mutex<PersistentState>> = OnceLock::new();
+
+fn with_state<R>(f: impl FnOnce(&mut PersistentState) -> R) -> R {
+    let lock = STATE.get_or_init(|| {
+        Mutex::new(PersistentState {
+            allocator: PersistentAllocator::new(ARENA_SIZE),
+            known_ids: Vec::new(),
+        })
+    });
+    let mut guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut guard)
+}
+
+/// Picks one id out of the full history — live or already freed — rather
+/// than only ever-live ones, so the op stream can target a dead id on
+/// purpose.
+fn pick_known_id(known_ids: &[AllocId], consumer: &mut FuzzConsumer) -> Option<AllocId> {
+    if known_ids.is_empty() {
+        return None;
+    }
+    let idx = consumer.consume_int_in_range(0, known_ids.len() as u64 - 1) as usize;
+    known_ids.get(idx).copied()
+}
+
+/// Persistent allocator fuzz target. Unlike `fuzz_allocator`, which resets
+/// its whole allocation list on every call, this replays each case's op
+/// stream against one `PersistentAllocator` and id history kept alive for
+/// the process's lifetime — the only way to reach a bug that needs a long
+/// alloc/free/realloc sequence to manifest.
+///
+/// Input format: repeated `[operation: enum(3)][fields...]`, same layout
+/// as `fuzz_allocator`'s alloc/free/realloc ops, except free/realloc
+/// select their target from the full persistent id history instead of the
+/// current call's own allocation list.
+pub fn fuzz_persistent_allocator(data: &[u8]) {
+    let mut consumer = FuzzConsumer::new(data);
+
+    with_state(|state| {
+        while consumer.remaining() > 0 {
+            match consumer.consume_enum(3) {
+                // Allocate
+                0 => {
+                    let size = consumer.consume_int_in_range(1, 0x100000) as usize;
+                    let align_shift = consumer.consume_int_in_range(3, 12);
+                    let align = 1usize << align_shift;
+                    if let Some(id) = state.allocator.alloc(size, align) {
+                        state.known_ids.push(id);
+                    }
+                }
+
+                // Free
+                1 => {
+                    if let Some(id) = pick_known_id(&state.known_ids, &mut consumer) {
+                        state.allocator.free(id);
+                    }
+                }
+
+                // Reallocate
+                2 => {
+                    if let Some(id) = pick_known_id(&state.known_ids, &mut consumer) {
+                        let new_size = consumer.consume_int_in_range(1, 0x100000) as usize;
+                        if let Some(new_id) = state.allocator.realloc(id, new_size, 8) {
+                            state.known_ids.push(new_id);
+                        }
+                    }
+                }
+
+                _ => unreachable!(),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc_free_realloc_round_trip() {
+        let mut allocator = PersistentAllocator::new(4096);
+        let id = allocator.alloc(64, 8).unwrap();
+        let new_id = allocator.realloc(id, 128, 8).unwrap();
+        allocator.free(new_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn double_free_aborts_instead_of_wrapping() {
+        let mut allocator = PersistentAllocator::new(4096);
+        let id = allocator.alloc(64, 8).unwrap();
+        allocator.free(id);
+        allocator.free(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "realloc of dead id")]
+    fn realloc_of_freed_id_aborts() {
+        let mut allocator = PersistentAllocator::new(4096);
+        let id = allocator.alloc(64, 8).unwrap();
+        allocator.free(id);
+        allocator.realloc(id, 128, 8);
+    }
+
+    #[test]
+    fn snapshot_restore_rewinds_state() {
+        let mut allocator = PersistentAllocator::new(4096);
+        let first = allocator.alloc(64, 8).unwrap();
+        let snapshot = allocator.snapshot();
+
+        allocator.free(first);
+        let _second = allocator.alloc(128, 8).unwrap();
+
+        allocator.restore(&snapshot);
+        // `first` should be live again post-restore, so freeing it now
+        // must succeed rather than panicking as a double free.
+        allocator.free(first);
+    }
+}
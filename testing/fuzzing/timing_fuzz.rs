@@ -0,0 +1,215 @@
+// Timing Side-Channel Fuzzing Mode
+//
+// The validation branches in `fuzz_syscall_dispatcher` (the layered
+// `fd < 0 || buf_addr >= ... || count > ...` checks) are exactly the kind
+// of early-return chain that leaks information through timing: an input
+// that fails the first check returns faster than one that clears several.
+// Coverage-guided fuzzing can't find that on its own since both inputs
+// hit the same lines of code and produce the same (non-)crash. This mode
+// instead evolves *pairs* of same-length inputs toward a measurable
+// timing gap, using Welch's t-test as the fitness function — the same
+// statistic `dudect`-style constant-time checkers use to tell "different
+// branch" apart from "just noise".
+
+use crate::fuzz_env::{EnvMode, FuzzEnv};
+use crate::fuzz_syscall_dispatcher;
+use crate::Rng;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Byte length of one dispatcher call: `[syscall_num: u64][arg1..arg6: u64]`.
+const INPUT_LEN: usize = 8 * 7;
+
+/// A candidate pair under evolution. Both inputs are always `INPUT_LEN`
+/// bytes, so only argument *values* (not sizes) can drive a timing gap.
+#[derive(Clone)]
+pub struct CandidatePair {
+    pub class_a: [u8; INPUT_LEN],
+    pub class_b: [u8; INPUT_LEN],
+}
+
+/// A pair whose measured timing gap cleared the reporting threshold.
+pub struct LeakCandidate {
+    pub class_a: [u8; INPUT_LEN],
+    pub class_b: [u8; INPUT_LEN],
+    pub t_statistic: f64,
+}
+
+/// Runs the dispatcher over `input` `trials` times, returning each
+/// wall-clock duration in nanoseconds. Each call gets a fresh `FuzzEnv` in
+/// `Live` mode (fixed, zeroed environment responses) so the measured time
+/// reflects only the dispatcher's own branching on `input`, not variation
+/// pulled from a deterministic-replay tail.
+fn measure_ns(input: &[u8; INPUT_LEN], trials: usize) -> Vec<f64> {
+    let syscall_num = u64::from_le_bytes(input[0..8].try_into().unwrap());
+    let mut args = [0u64; 6];
+    for (i, arg) in args.iter_mut().enumerate() {
+        let offset = 8 + i * 8;
+        *arg = u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap());
+    }
+
+    let mut samples = Vec::with_capacity(trials);
+    for _ in 0..trials {
+        let mut env = FuzzEnv::new(&[], EnvMode::Live);
+        let start = Instant::now();
+        black_box(fuzz_syscall_dispatcher(black_box(syscall_num), black_box(&args), &mut env));
+        samples.push(start.elapsed().as_nanos() as f64);
+    }
+    samples
+}
+
+/// Trims the top/bottom 10% of `samples` (scheduler jitter and cache
+/// misses show up as long-tail outliers, not as a shifted mean) and
+/// returns `(mean, variance, n)` of what's left.
+fn trimmed_mean_var(mut samples: Vec<f64>) -> (f64, f64, usize) {
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let trim = samples.len() / 10;
+    let slice = if samples.len() > 2 * trim {
+        &samples[trim..samples.len() - trim]
+    } else {
+        &samples[..]
+    };
+
+    let n = slice.len().max(1);
+    let mean = slice.iter().sum::<f64>() / n as f64;
+    let var = slice.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+    (mean, var, n)
+}
+
+/// Welch's t-statistic between two independent samples of possibly
+/// unequal variance: `(meanA - meanB) / sqrt(varA/nA + varB/nB)`.
+fn welchs_t(mean_a: f64, var_a: f64, n_a: usize, mean_b: f64, var_b: f64, n_b: usize) -> f64 {
+    let denom = (var_a / n_a as f64 + var_b / n_b as f64).sqrt();
+    if denom == 0.0 {
+        0.0
+    } else {
+        (mean_a - mean_b) / denom
+    }
+}
+
+/// Measures both halves of `pair` and returns the Welch's t-statistic
+/// between them; fitness for the genetic search is `|t|`.
+fn score_pair(pair: &CandidatePair, trials: usize) -> f64 {
+    let (mean_a, var_a, n_a) = trimmed_mean_var(measure_ns(&pair.class_a, trials));
+    let (mean_b, var_b, n_b) = trimmed_mean_var(measure_ns(&pair.class_b, trials));
+    welchs_t(mean_a, var_a, n_a, mean_b, var_b, n_b)
+}
+
+fn random_input(rng: &mut Rng) -> [u8; INPUT_LEN] {
+    let mut buf = [0u8; INPUT_LEN];
+    for byte in &mut buf {
+        *byte = rng.gen_range(256) as u8;
+    }
+    buf
+}
+
+fn random_pair(rng: &mut Rng) -> CandidatePair {
+    CandidatePair { class_a: random_input(rng), class_b: random_input(rng) }
+}
+
+/// Produces a child pair by per-byte crossover between two parents,
+/// followed by a single mutated byte in each half. Both halves stay
+/// `INPUT_LEN` bytes by construction, preserving the same-length
+/// invariant.
+fn crossover_and_mutate(a: &CandidatePair, b: &CandidatePair, rng: &mut Rng) -> CandidatePair {
+    let mut child = a.clone();
+    for i in 0..INPUT_LEN {
+        if rng.gen_range(2) == 0 {
+            child.class_a[i] = b.class_a[i];
+            child.class_b[i] = b.class_b[i];
+        }
+    }
+
+    let idx_a = rng.gen_range(INPUT_LEN);
+    child.class_a[idx_a] = rng.gen_range(256) as u8;
+    let idx_b = rng.gen_range(INPUT_LEN);
+    child.class_b[idx_b] = rng.gen_range(256) as u8;
+
+    child
+}
+
+/// Default `|t|` reporting threshold for `run_timing_fuzz`.
+pub const DEFAULT_LEAK_THRESHOLD: f64 = 4.5;
+
+/// Evolves a population of candidate pairs over `generations` rounds,
+/// scoring each by `|welchs_t|` against `trials_per_measurement` timing
+/// samples per half, and collects every pair whose `|t|` exceeds
+/// `threshold` (roughly p<0.001 at the default of
+/// `DEFAULT_LEAK_THRESHOLD`) across all generations as a candidate timing
+/// leak.
+pub fn run_timing_fuzz(
+    population_size: usize,
+    generations: usize,
+    trials_per_measurement: usize,
+    threshold: f64,
+    seed: u32,
+) -> Vec<LeakCandidate> {
+    let mut rng = Rng::new(seed);
+    let mut population: Vec<CandidatePair> = (0..population_size).map(|_| random_pair(&mut rng)).collect();
+    let mut leaks = Vec::new();
+
+    for _generation in 0..generations {
+        let mut scored: Vec<(f64, CandidatePair)> = population
+            .into_iter()
+            .map(|pair| {
+                let t = score_pair(&pair, trials_per_measurement);
+                (t, pair)
+            })
+            .collect();
+        scored.sort_by(|(t_a, _), (t_b, _)| t_b.abs().partial_cmp(&t_a.abs()).unwrap());
+
+        for (t, pair) in &scored {
+            if t.abs() > threshold {
+                leaks.push(LeakCandidate { class_a: pair.class_a, class_b: pair.class_b, t_statistic: *t });
+            }
+        }
+
+        let survivor_count = (population_size / 2).max(1);
+        let survivors: Vec<CandidatePair> = scored.into_iter().take(survivor_count).map(|(_, pair)| pair).collect();
+
+        let mut next_generation = survivors.clone();
+        while next_generation.len() < population_size {
+            let parent_a = &survivors[rng.gen_range(survivors.len())];
+            let parent_b = &survivors[rng.gen_range(survivors.len())];
+            next_generation.push(crossover_and_mutate(parent_a, parent_b, &mut rng));
+        }
+        population = next_generation;
+    }
+
+    leaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welchs_t_is_zero_for_identical_distributions() {
+        assert_eq!(welchs_t(100.0, 4.0, 50, 100.0, 4.0, 50), 0.0);
+    }
+
+    #[test]
+    fn welchs_t_is_large_for_clearly_separated_distributions() {
+        let t = welchs_t(1000.0, 1.0, 100, 100.0, 1.0, 100);
+        assert!(t.abs() > 100.0, "expected a large t-statistic, got {}", t);
+    }
+
+    #[test]
+    fn trimmed_mean_var_drops_a_single_outlier() {
+        let mut samples = vec![100.0; 20];
+        samples.push(1_000_000.0);
+        let (mean, _, n) = trimmed_mean_var(samples);
+        assert!(mean < 200.0, "outlier should have been trimmed, got mean {}", mean);
+        assert!(n < 21);
+    }
+
+    #[test]
+    fn crossover_and_mutate_preserves_input_length() {
+        let mut rng = Rng::new(7);
+        let a = random_pair(&mut rng);
+        let b = random_pair(&mut rng);
+        let child = crossover_and_mutate(&a, &b, &mut rng);
+        assert_eq!(child.class_a.len(), INPUT_LEN);
+        assert_eq!(child.class_b.len(), INPUT_LEN);
+    }
+}
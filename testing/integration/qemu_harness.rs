@@ -0,0 +1,307 @@
+// QEMU Serial-Output Test Harness for Neuro-OS
+//
+// Integration tests that boot a kernel image under QEMU need a real signal
+// for pass/fail instead of a fixed sleep. This module captures the guest's
+// `-serial stdio` pipe on a background reader thread, watches for a
+// boot-complete marker, and then parses the KTAP/TAP stream the in-VM test
+// runner emits (mirroring how kselftest-style in-kernel test reporting
+// works) into a structured `TestReport`.
+//
+// Usage:
+//   let report = QemuHarness::new(child)
+//       .boot_marker("Neuro-OS: init started")
+//       .timeout(Duration::from_secs(30))
+//       .run();
+//   assert_eq!(report.failed, 0);
+
+use std::io::{BufRead, BufReader};
+use std::process::Child;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Outcome of streaming a QEMU guest's serial output through the KTAP parser.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestReport {
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    /// Set when a kernel panic/oops pattern was observed or the wall-clock
+    /// timeout fired; the harness kills the child in either case.
+    pub crashed: bool,
+    /// The last `tail_lines` lines of output (serial stdout and QEMU's
+    /// stderr, interleaved as received), kept for diagnosis regardless of
+    /// outcome.
+    pub tail: Vec<String>,
+}
+
+impl TestReport {
+    fn is_passing(&self) -> bool {
+        !self.crashed && self.failed == 0
+    }
+}
+
+/// Lines emitted on the reader threads, forwarded to the harness loop.
+enum LogLine {
+    Line(String),
+    /// A line from the child's stderr pipe, kept for diagnosis but never fed
+    /// to the KTAP parser — it's QEMU's own chatter, not guest test output.
+    StderrLine(String),
+    /// The child's stdout pipe closed (process exited or was killed).
+    Eof,
+}
+
+/// Patterns that mean "the guest died", independent of TAP bookkeeping.
+const PANIC_MARKERS: &[&str] = &["kernel panic", "Kernel panic", "RIP:", "CPU: "];
+
+/// Drives a QEMU child process to completion, classifying its serial output.
+pub struct QemuHarness {
+    child: Child,
+    boot_marker: String,
+    timeout: Duration,
+    tail_lines: usize,
+}
+
+impl QemuHarness {
+    pub fn new(child: Child) -> Self {
+        QemuHarness {
+            child,
+            boot_marker: String::from("Neuro-OS: init started"),
+            timeout: Duration::from_secs(30),
+            tail_lines: 40,
+        }
+    }
+
+    /// Sets the line that marks successful boot; KTAP parsing only begins
+    /// once this has been seen, so pre-boot kernel chatter is never
+    /// mistaken for test output.
+    pub fn boot_marker(mut self, marker: impl Into<String>) -> Self {
+        self.boot_marker = marker.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs the harness to completion: reads serial output until the guest
+    /// exits, a panic marker fires, or the timeout elapses, then returns the
+    /// accumulated `TestReport`. The child is always killed before returning.
+    pub fn run(mut self) -> TestReport {
+        let stdout = self
+            .child
+            .stdout
+            .take()
+            .expect("QEMU child must be spawned with Stdio::piped() stdout");
+
+        let (tx, rx) = mpsc::channel();
+
+        // QEMU is spawned with stderr piped too; if nothing drains it, the
+        // pipe buffer fills and QEMU blocks on write() while we block on
+        // reading stdout, hanging the whole harness. Drain it on its own
+        // thread just like stdout, but don't feed it to the KTAP parser.
+        if let Some(stderr) = self.child.stderr.take() {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let reader = BufReader::new(stderr);
+                for line in reader.lines().map_while(Result::ok) {
+                    if tx.send(LogLine::StderrLine(line)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        std::thread::spawn(move || {
+            let reader = BufReader::new(stdout);
+            for line in reader.lines() {
+                match line {
+                    Ok(line) => {
+                        if tx.send(LogLine::Line(line)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            let _ = tx.send(LogLine::Eof);
+        });
+
+        let mut parser = KtapParser::new();
+        let mut tail: Vec<String> = Vec::new();
+        let mut booted = false;
+        let start = Instant::now();
+        let mut crashed = false;
+
+        loop {
+            let remaining = self.timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                crashed = true;
+                break;
+            }
+
+            match rx.recv_timeout(remaining) {
+                Ok(LogLine::Line(line)) => {
+                    push_tail(&mut tail, line.clone(), self.tail_lines);
+
+                    if PANIC_MARKERS.iter().any(|marker| line.contains(marker)) {
+                        crashed = true;
+                        break;
+                    }
+
+                    if !booted {
+                        if line.contains(&self.boot_marker) {
+                            booted = true;
+                        }
+                        continue;
+                    }
+
+                    parser.feed_line(&line);
+                }
+                Ok(LogLine::StderrLine(line)) => {
+                    push_tail(&mut tail, line.clone(), self.tail_lines);
+
+                    if PANIC_MARKERS.iter().any(|marker| line.contains(marker)) {
+                        crashed = true;
+                        break;
+                    }
+                }
+                Ok(LogLine::Eof) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    crashed = true;
+                    break;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+
+        let mut report = parser.into_report();
+        report.crashed = report.crashed || crashed;
+        report.tail = tail;
+        report
+    }
+}
+
+/// True if `rest` (the text immediately after a stripped `"ok"`/`"not ok"`
+/// prefix) marks a genuine TAP result line rather than some other line that
+/// merely happens to start with those letters, e.g. `"okay, continuing boot
+/// sequence"`. A real result line is followed by whitespace (then the test
+/// id) or is the whole line.
+fn is_token_boundary(rest: &str) -> bool {
+    rest.is_empty() || rest.starts_with(char::is_whitespace)
+}
+
+fn push_tail(tail: &mut Vec<String>, line: String, max: usize) {
+    tail.push(line);
+    if tail.len() > max {
+        tail.remove(0);
+    }
+}
+
+/// Streaming KTAP/TAP parser.
+///
+/// Recognizes the subset of TAP emitted by in-VM test runners:
+/// - `KTAP version 1` / `TAP version 13` preamble
+/// - `1..N` plan lines
+/// - `ok <n> - <name>` and `not ok <n> - <name>` result lines, optionally
+///   with a trailing `# SKIP <reason>` directive
+/// - Subtests nested via leading whitespace indentation
+struct KtapParser {
+    report: TestReport,
+}
+
+impl KtapParser {
+    fn new() -> Self {
+        KtapParser {
+            report: TestReport::default(),
+        }
+    }
+
+    fn feed_line(&mut self, raw: &str) {
+        // Nested subtests are indented; strip leading whitespace so `ok`/`not
+        // ok` lines are recognized regardless of nesting depth. We don't
+        // track the subtest hierarchy itself, only roll results up.
+        let line = raw.trim_start();
+
+        if line.starts_with("KTAP version") || line.starts_with("TAP version") {
+            return;
+        }
+        if line.starts_with(|c: char| c.is_ascii_digit()) && line.contains("..") {
+            // Plan line, e.g. "1..5" — nothing to record.
+            return;
+        }
+
+        if let Some(rest) = line.strip_prefix("not ok").filter(|rest| is_token_boundary(rest)) {
+            if rest.contains("# SKIP") {
+                self.report.skipped += 1;
+            } else {
+                self.report.failed += 1;
+            }
+        } else if let Some(rest) = line.strip_prefix("ok").filter(|rest| is_token_boundary(rest)) {
+            if rest.contains("# SKIP") {
+                self.report.skipped += 1;
+            } else {
+                self.report.passed += 1;
+            }
+        }
+    }
+
+    fn into_report(self) -> TestReport {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&str]) -> TestReport {
+        let mut parser = KtapParser::new();
+        for line in lines {
+            parser.feed_line(line);
+        }
+        parser.into_report()
+    }
+
+    #[test]
+    fn counts_pass_fail_skip() {
+        let report = parse(&[
+            "KTAP version 1",
+            "1..3",
+            "ok 1 - test_alpha",
+            "not ok 2 - test_beta",
+            "ok 3 - test_gamma # SKIP not applicable",
+        ]);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.skipped, 1);
+        assert!(report.is_passing() == false);
+    }
+
+    #[test]
+    fn handles_nested_indentation() {
+        let report = parse(&["KTAP version 1", "    ok 1 - nested_test", "not ok 2 - outer"]);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 1);
+    }
+
+    #[test]
+    fn all_passing_is_passing() {
+        let report = parse(&["ok 1 - a", "ok 2 - b"]);
+        assert!(report.is_passing());
+    }
+
+    #[test]
+    fn chatter_lines_starting_with_ok_are_not_counted() {
+        let report = parse(&[
+            "okay, continuing boot sequence",
+            "broker: ok, accepting connections",
+            "ok 1 - real_test",
+        ]);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failed, 0);
+    }
+}
@@ -0,0 +1,308 @@
+// Linearizability Checker for Neuro-OS Concurrent Histories
+//
+// `prop_ipc_message_order` and `prop_refcount_prevents_uaf` only model
+// single-threaded behavior, but the interesting OS bugs live in concurrent
+// interleavings. This module takes a recorded *concurrent* history — ops
+// that may overlap in real time — and checks whether some sequential
+// ordering of it, consistent with each op's real-time span, matches a
+// sequential specification (a FIFO queue for IPC, a counter for refcounts).
+//
+// The search follows Wing & Gong's approach: repeatedly linearize a
+// *minimal* op (one whose invocation precedes every still-pending op's
+// response, so placing it next cannot violate real-time order), apply it to
+// the spec, and recurse; backtrack and try the next minimal op if the
+// recorded result doesn't match. Memoizing on (remaining ops, spec state)
+// keeps this from re-exploring the same search space twice.
+
+use quickcheck::{Arbitrary, Gen};
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// One call in a concurrent history: `op` was invoked at `invoke` and
+/// observed to return `result` at `response`, with `invoke <= response`.
+/// Two entries with overlapping `[invoke, response]` spans may have
+/// happened in either order as far as the checker is concerned.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry<Op, Res> {
+    pub op: Op,
+    pub invoke: u64,
+    pub response: u64,
+    pub result: Res,
+}
+
+/// A sequential specification: a state machine whose `apply` is the
+/// reference behavior a linearizable concurrent history must be consistent
+/// with under *some* per-op interleaving.
+pub trait Spec: Clone + Eq + Hash {
+    type Op: Clone;
+    type Res: Clone + PartialEq;
+
+    fn apply(&mut self, op: &Self::Op) -> Self::Res;
+}
+
+/// Checks whether `history` is linearizable against `spec`: is there an
+/// ordering of its entries, each respecting real-time precedence (no entry
+/// is placed before one whose response happened before its invocation),
+/// under which replaying the ops against `spec` reproduces every recorded
+/// result?
+///
+/// Returns `Ok(())` if linearizable, or `Err(prefix_len)` giving the length
+/// of the smallest prefix (by recorded history index) that already admits
+/// no valid linearization — the standard way to report a counterexample
+/// without dumping the whole search.
+pub fn check_linearizable<S: Spec>(
+    spec: S,
+    history: &[HistoryEntry<S::Op, S::Res>],
+) -> Result<(), usize> {
+    let mut memo: HashSet<(Vec<bool>, S)> = HashSet::new();
+    let remaining: Vec<bool> = vec![true; history.len()];
+
+    if search(&spec, history, remaining, &mut memo) {
+        Ok(())
+    } else {
+        smallest_failing_prefix(spec, history)
+    }
+}
+
+/// Recursive linearization search over which entries remain to be placed.
+fn search<S: Spec>(
+    state: &S,
+    history: &[HistoryEntry<S::Op, S::Res>],
+    remaining: Vec<bool>,
+    memo: &mut HashSet<(Vec<bool>, S)>,
+) -> bool {
+    if remaining.iter().all(|&r| !r) {
+        return true;
+    }
+
+    let key = (remaining.clone(), state.clone());
+    if memo.contains(&key) {
+        return false;
+    }
+
+    for i in 0..history.len() {
+        if !remaining[i] {
+            continue;
+        }
+        if !is_minimal(i, &remaining, history) {
+            continue;
+        }
+
+        let mut candidate_state = state.clone();
+        let result = candidate_state.apply(&history[i].op);
+
+        if result == history[i].result {
+            let mut next_remaining = remaining.clone();
+            next_remaining[i] = false;
+
+            if search(&candidate_state, history, next_remaining, memo) {
+                return true;
+            }
+        }
+    }
+
+    memo.insert(key);
+    false
+}
+
+/// An entry is minimal if no other still-pending entry's response happened
+/// at or before this entry's invocation — i.e. placing it next cannot
+/// reorder it before something real time says must come first. The bound
+/// is inclusive: `entry.response == candidate.invoke` still means `entry`
+/// finished no later than `candidate` started, so the two never actually
+/// overlapped and real time already orders them.
+fn is_minimal<Op, Res>(i: usize, remaining: &[bool], history: &[HistoryEntry<Op, Res>]) -> bool {
+    let candidate = &history[i];
+    for (j, entry) in history.iter().enumerate() {
+        if j == i || !remaining[j] {
+            continue;
+        }
+        if entry.response <= candidate.invoke {
+            // entry must have already completed before candidate started;
+            // since entry is still "remaining" it hasn't been placed, so
+            // candidate cannot be minimal.
+            return false;
+        }
+    }
+    true
+}
+
+/// Finds the smallest prefix of `history` (by recorded index) that already
+/// has no valid linearization, by growing the prefix until the search fails.
+fn smallest_failing_prefix<S: Spec>(spec: S, history: &[HistoryEntry<S::Op, S::Res>]) -> Result<(), usize> {
+    for len in 1..=history.len() {
+        let prefix = &history[..len];
+        let mut memo = HashSet::new();
+        let remaining = vec![true; prefix.len()];
+        if !search(&spec, prefix, remaining, &mut memo) {
+            return Err(len);
+        }
+    }
+    // Unreachable in practice: the full history already failed the caller's
+    // check, so some prefix must fail too. Fall back to the whole history.
+    Err(history.len())
+}
+
+/// A FIFO queue spec for IPC message histories: `push`/`pop` ops where
+/// `pop` observes `None` on an empty queue.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct FifoQueueSpec {
+    queue: Vec<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueueOp {
+    Push(u64),
+    Pop,
+}
+
+impl Spec for FifoQueueSpec {
+    type Op = QueueOp;
+    type Res = Option<u64>;
+
+    fn apply(&mut self, op: &QueueOp) -> Option<u64> {
+        match op {
+            QueueOp::Push(v) => {
+                self.queue.push(*v);
+                None
+            }
+            QueueOp::Pop => {
+                if self.queue.is_empty() {
+                    None
+                } else {
+                    Some(self.queue.remove(0))
+                }
+            }
+        }
+    }
+}
+
+/// A saturating counter spec for refcount histories: `incr`/`decr` ops,
+/// where `decr` below zero is rejected rather than going negative.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RefcountSpec {
+    count: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RefcountOp {
+    Incr,
+    Decr,
+}
+
+impl Spec for RefcountSpec {
+    type Op = RefcountOp;
+    type Res = bool;
+
+    fn apply(&mut self, op: &RefcountOp) -> bool {
+        match op {
+            RefcountOp::Incr => {
+                self.count += 1;
+                true
+            }
+            RefcountOp::Decr => {
+                if self.count > 0 {
+                    self.count -= 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// A randomly generated, possibly-overlapping history of `FifoQueueSpec`
+/// operations, recorded by replaying a sequential interleaving against the
+/// spec itself so every entry's result is genuinely achievable — the
+/// checker's job is then to confirm it can *find* that linearization even
+/// after the real-time spans are scrambled to overlap.
+#[derive(Clone, Debug)]
+pub struct FifoHistory(pub Vec<HistoryEntry<QueueOp, Option<u64>>>);
+
+impl Arbitrary for FifoHistory {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let len = (usize::arbitrary(g) % 16) + 1;
+        let mut spec = FifoQueueSpec { queue: Vec::new() };
+        let mut entries = Vec::with_capacity(len);
+        let mut clock = 0u64;
+
+        for _ in 0..len {
+            let op = if bool::arbitrary(g) {
+                QueueOp::Push(u64::arbitrary(g) % 1000)
+            } else {
+                QueueOp::Pop
+            };
+
+            let invoke = clock;
+            // Overlap spans randomly so invocation order need not match
+            // response order; the checker must still find a valid
+            // real-time-respecting linearization.
+            let response = clock + (u64::arbitrary(g) % 3) + 1;
+            clock += 1;
+
+            let result = spec.apply(&op);
+            entries.push(HistoryEntry { op, invoke, response, result });
+        }
+
+        FifoHistory(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn sequential_history_is_linearizable() {
+        let history = vec![
+            HistoryEntry { op: QueueOp::Push(1), invoke: 0, response: 1, result: None },
+            HistoryEntry { op: QueueOp::Push(2), invoke: 1, response: 2, result: None },
+            HistoryEntry { op: QueueOp::Pop, invoke: 2, response: 3, result: Some(1) },
+        ];
+        assert!(check_linearizable(FifoQueueSpec { queue: Vec::new() }, &history).is_ok());
+    }
+
+    #[test]
+    fn non_fifo_result_is_rejected() {
+        // Push(1) then Push(2) fully precede Pop in real time, so FIFO order
+        // forces Pop to observe 1 first; claiming it observed 2 is a lie no
+        // linearization can satisfy.
+        let history = vec![
+            HistoryEntry { op: QueueOp::Push(1), invoke: 0, response: 1, result: None },
+            HistoryEntry { op: QueueOp::Push(2), invoke: 1, response: 2, result: None },
+            HistoryEntry { op: QueueOp::Pop, invoke: 3, response: 4, result: Some(2) },
+        ];
+        assert_eq!(
+            check_linearizable(FifoQueueSpec { queue: Vec::new() }, &history),
+            Err(3)
+        );
+    }
+
+    #[test]
+    fn overlapping_pushes_can_linearize_either_order() {
+        // Two pushes overlap in real time; a Pop after both completed may
+        // legally observe either value depending on which linearizes first.
+        let history = vec![
+            HistoryEntry { op: QueueOp::Push(1), invoke: 0, response: 2, result: None },
+            HistoryEntry { op: QueueOp::Push(2), invoke: 1, response: 3, result: None },
+            HistoryEntry { op: QueueOp::Pop, invoke: 4, response: 5, result: Some(2) },
+        ];
+        assert!(check_linearizable(FifoQueueSpec { queue: Vec::new() }, &history).is_ok());
+    }
+
+    #[test]
+    fn refcount_rejects_decrement_below_zero() {
+        let history = vec![HistoryEntry { op: RefcountOp::Decr, invoke: 0, response: 1, result: true }];
+        assert!(check_linearizable(RefcountSpec { count: 0 }, &history).is_err());
+    }
+
+    #[test]
+    fn generated_histories_are_linearizable() {
+        fn prop(history: FifoHistory) -> bool {
+            check_linearizable(FifoQueueSpec { queue: Vec::new() }, &history.0).is_ok()
+        }
+        quickcheck(prop as fn(FifoHistory) -> bool);
+    }
+}
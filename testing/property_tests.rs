@@ -18,51 +18,115 @@
 //     }
 
 use quickcheck::{Arbitrary, Gen, QuickCheck, TestResult};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
+/// The page-table architectures this crate's targets care about.
+///
+/// Each has a different canonical-address rule and a different page-table
+/// depth, both of which `VirtualAddress` generation and `PageTable` walking
+/// must respect so generated addresses and translations are architecturally
+/// legal rather than an x86_64-only approximation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    X86_64,
+    Sv39,
+    Sv48,
+}
+
+impl Arch {
+    /// The highest bit of the "low" canonical region; every bit above this
+    /// one must equal it (sign-extension) for an address to be canonical.
+    fn canonical_bit(self) -> u32 {
+        match self {
+            Arch::X86_64 => 47,
+            Arch::Sv39 => 38,
+            Arch::Sv48 => 47,
+        }
+    }
+
+    /// Number of page-table levels walked, each indexed by 9 VPN bits.
+    fn levels(self) -> u32 {
+        match self {
+            Arch::X86_64 | Arch::Sv48 => 4,
+            Arch::Sv39 => 3,
+        }
+    }
+}
+
+impl Arbitrary for Arch {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match u8::arbitrary(g) % 3 {
+            0 => Arch::X86_64,
+            1 => Arch::Sv39,
+            _ => Arch::Sv48,
+        }
+    }
+}
+
+/// Generates a page-aligned, architecturally canonical address for `arch`:
+/// a random value is drawn for the bits below (and including) the arch's
+/// canonical bit, then every bit above that is sign-extended from it, which
+/// is exactly the hardware's canonical-form check in reverse.
+fn canonical_address(arch: Arch, g: &mut Gen) -> u64 {
+    let bit = arch.canonical_bit();
+    let low_mask = (1u64 << (bit + 1)) - 1;
+
+    let low = u64::arbitrary(g) & low_mask & !0xFFF;
+    let sign = (low >> bit) & 1;
+
+    if sign == 1 {
+        low | !low_mask
+    } else {
+        low
+    }
+}
+
 /// Custom arbitrary data generator for kernel virtual addresses.
-/// 
+///
 /// Virtual addresses in Neuro-OS must satisfy certain constraints:
 /// - Must be aligned to page boundaries (4KB = 0x1000)
-/// - Must be within valid kernel address space (0xFFFF_8000_0000_0000 - 0xFFFF_FFFF_FFFF_FFFF)
+/// - Must be in canonical form for their architecture (`arch`): x86_64 and
+///   Sv48 sign-extend from bit 47, Sv39 sign-extends from bit 38
 /// - Must not overlap with reserved regions
 ///
 /// This generator ensures all generated addresses meet these requirements.
 #[derive(Clone, Debug)]
-pub struct VirtualAddress(pub u64);
+pub struct VirtualAddress {
+    pub arch: Arch,
+    pub addr: u64,
+}
 
 impl Arbitrary for VirtualAddress {
     fn arbitrary(g: &mut Gen) -> Self {
-        // Generate a random offset within kernel space
-        let offset = u64::arbitrary(g) % 0x0000_7FFF_FFFF_F000;
-        
-        // Base kernel address + aligned offset
-        let addr = 0xFFFF_8000_0000_0000 + (offset & !0xFFF);
-        
-        VirtualAddress(addr)
+        let arch = Arch::arbitrary(g);
+        let addr = canonical_address(arch, g);
+        VirtualAddress { arch, addr }
     }
-    
+
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
-        // Shrink by moving towards the base kernel address
-        let base = 0xFFFF_8000_0000_0000;
-        let current = self.0;
-        
-        if current <= base {
+        // Shrink towards address 0, which is canonical for every arch, by
+        // repeatedly halving the low-bits magnitude and re-deriving the
+        // canonical high bits from the result.
+        let bit = self.arch.canonical_bit();
+        let low_mask = (1u64 << (bit + 1)) - 1;
+
+        let mut low = self.addr & low_mask;
+        if low == 0 {
             return Box::new(std::iter::empty());
         }
-        
-        // Generate intermediate addresses by halving the distance
+
         let mut candidates = Vec::new();
-        let mut addr = current;
-        
-        while addr > base {
-            let offset = addr - base;
-            addr = base + (offset / 2) & !0xFFF;
-            if addr != current {
-                candidates.push(VirtualAddress(addr));
+        while low > 0 {
+            low = (low / 2) & !0xFFF;
+            let sign = (low >> bit) & 1;
+            let addr = if sign == 1 { low | !low_mask } else { low };
+
+            if addr != self.addr {
+                candidates.push(VirtualAddress { arch: self.arch, addr });
             }
         }
-        
+
         Box::new(candidates.into_iter())
     }
 }
@@ -81,7 +145,7 @@ pub struct MemoryRegion {
 
 impl Arbitrary for MemoryRegion {
     fn arbitrary(g: &mut Gen) -> Self {
-        let start_addr = VirtualAddress::arbitrary(g).0;
+        let start_addr = VirtualAddress::arbitrary(g).addr;
         
         // Generate size in pages (1 to 1024 pages = 4KB to 4MB)
         let num_pages = (u64::arbitrary(g) % 1024) + 1;
@@ -144,26 +208,410 @@ pub fn prop_no_memory_overlap(regions: Vec<MemoryRegion>) -> TestResult {
     TestResult::passed()
 }
 
-/// Property: Virtual address translation should be reversible.
+/// A single operation in a generated allocator command sequence.
 ///
-/// If we translate a virtual address to a physical address and back,
-/// we should get the original virtual address. This ensures the page
-/// table implementation is consistent.
-pub fn prop_address_translation_reversible(vaddr: VirtualAddress) -> bool {
-    // Simulate page table lookup (simplified)
-    let page_offset = vaddr.0 & 0xFFF;
-    let vpn = vaddr.0 >> 12;
-    
-    // Mock physical address (in real implementation, this would be a page table walk)
-    let ppn = vpn ^ 0xAAAA_AAAA_AAAA;
-    let paddr = (ppn << 12) | page_offset;
-    
-    // Reverse translation
-    let reverse_ppn = paddr >> 12;
-    let reverse_vpn = reverse_ppn ^ 0xAAAA_AAAA_AAAA;
-    let reverse_vaddr = (reverse_vpn << 12) | page_offset;
-    
-    reverse_vaddr == vaddr.0
+/// `Free` refers to a previously allocated block by its *index* among the
+/// currently-live allocations (not a raw handle), so every generated
+/// sequence is well-formed by construction: there is no way to reference a
+/// handle that doesn't exist yet.
+#[derive(Clone, Debug)]
+pub enum Op {
+    Alloc { size: u64, align: u64 },
+    Free { live_index: usize },
+}
+
+/// A command sequence for the stateful allocator model.
+///
+/// Wrapping `Vec<Op>` (rather than implementing `Arbitrary` directly for it)
+/// lets us give it bespoke generation and shrinking: plain `Vec<Op>::arbitrary`
+/// would produce `Free` indices unrelated to how many allocations are live at
+/// that point in the sequence, so almost every generated case would be
+/// rejected before exercising anything interesting.
+#[derive(Clone, Debug)]
+pub struct OpSequence(pub Vec<Op>);
+
+impl Arbitrary for OpSequence {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let len = usize::arbitrary(g) % 64;
+        let mut ops = Vec::with_capacity(len);
+        let mut live = 0usize;
+
+        for _ in 0..len {
+            // Bias towards Alloc so sequences actually build up live state
+            // instead of immediately running out of things to free.
+            let want_free = live > 0 && bool::arbitrary(g) && bool::arbitrary(g);
+
+            if want_free {
+                let live_index = usize::arbitrary(g) % live;
+                ops.push(Op::Free { live_index });
+                live -= 1;
+            } else {
+                let size = (u64::arbitrary(g) % 0x10000) + 1;
+                let align_shift = u8::arbitrary(g) % 8; // 1, 2, 4, ... 128
+                let align = 1u64 << align_shift;
+                ops.push(Op::Alloc { size, align });
+                live += 1;
+            }
+        }
+
+        OpSequence(ops)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let ops = self.0.clone();
+        let mut candidates = Vec::new();
+
+        // Remove one op at a time, rewriting later `Free` live_indices so the
+        // sequence stays well-formed (after a removed Alloc, every later
+        // Free's index shifts down by one).
+        for removed_idx in 0..ops.len() {
+            if let Some(rewritten) = remove_op_well_formed(&ops, removed_idx) {
+                candidates.push(OpSequence(rewritten));
+            }
+        }
+
+        // Shrink individual Alloc sizes towards smaller powers matching
+        // `MemoryRegion::shrink`'s halving strategy.
+        for i in 0..ops.len() {
+            if let Op::Alloc { size, align } = ops[i] {
+                if size > 1 {
+                    let mut shrunk = ops.clone();
+                    shrunk[i] = Op::Alloc { size: size / 2, align };
+                    candidates.push(OpSequence(shrunk));
+                }
+            }
+        }
+
+        Box::new(candidates.into_iter())
+    }
+}
+
+/// Removes `ops[removed_idx]` and rewrites every later `Free { live_index }`
+/// so the sequence remains well-formed, or returns `None` if removing it
+/// would leave a `Free` with nothing to reference.
+///
+/// Each `Free` consumes one unit of "live" allocations. Removing an `Alloc`
+/// shifts every later `Free` index down by one (a later `Free` at index 0
+/// gives `None`); removing a `Free` leaves later indices unchanged, since
+/// one more allocation then stays live. Only range is preserved: a
+/// rewritten `Free` may refer to a different allocation than before.
+fn remove_op_well_formed(ops: &[Op], removed_idx: usize) -> Option<Vec<Op>> {
+    let removed_is_alloc = matches!(ops[removed_idx], Op::Alloc { .. });
+    let mut out = Vec::with_capacity(ops.len() - 1);
+    let mut live = 0usize;
+
+    for (i, op) in ops.iter().enumerate() {
+        if i == removed_idx {
+            continue;
+        }
+        match *op {
+            Op::Alloc { size, align } => {
+                out.push(Op::Alloc { size, align });
+                live += 1;
+            }
+            Op::Free { live_index } => {
+                let adjusted = if removed_is_alloc && i > removed_idx {
+                    // An Alloc before this Free vanished, so the live set is
+                    // one smaller: shift the index down by one.
+                    live_index.checked_sub(1)
+                } else {
+                    Some(live_index)
+                };
+
+                let adjusted = adjusted?;
+                if adjusted >= live {
+                    return None;
+                }
+                out.push(Op::Free { live_index: adjusted });
+                live -= 1;
+            }
+        }
+    }
+
+    Some(out)
+}
+
+/// A trivial reference allocator: a sorted list of live `(start, size)`
+/// blocks inside a fixed arena, always placed at the lowest address with
+/// enough room to satisfy size and alignment (first-fit). This crate has no
+/// real allocator to link against yet, so this also stands in for the
+/// system-under-test (mirroring the mock page-table approach taken by
+/// `prop_address_translation_reversible` below) — swap in the real
+/// allocator here once one exists and the invariant checks below keep it
+/// honest unchanged.
+struct ReferenceAllocator {
+    arena_size: u64,
+    live: Vec<(u64, u64)>, // sorted by start
+}
+
+impl ReferenceAllocator {
+    fn new(arena_size: u64) -> Self {
+        ReferenceAllocator { arena_size, live: Vec::new() }
+    }
+
+    fn alloc(&mut self, size: u64, align: u64) -> Option<u64> {
+        let mut cursor = 0u64;
+        for &(start, blk_size) in &self.live {
+            let aligned = align_up(cursor, align);
+            if aligned + size <= start {
+                return Some(self.insert(aligned, size));
+            }
+            cursor = cursor.max(start + blk_size);
+        }
+        let aligned = align_up(cursor, align);
+        if aligned + size <= self.arena_size {
+            Some(self.insert(aligned, size))
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, start: u64, size: u64) -> u64 {
+        let pos = self.live.partition_point(|&(s, _)| s < start);
+        self.live.insert(pos, (start, size));
+        start
+    }
+
+    fn free(&mut self, start: u64) -> bool {
+        if let Some(pos) = self.live.iter().position(|&(s, _)| s == start) {
+            self.live.remove(pos);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) & !(align - 1)
+}
+
+/// Property: replaying a generated alloc/free command sequence against the
+/// system under test never violates the allocator's cross-cutting
+/// invariants, checked against a reference model in lockstep.
+///
+/// This exercises the actual alloc/free *lifecycle* rather than a one-shot
+/// set of regions, so it can catch fragmentation and double-free bugs that
+/// `prop_no_memory_overlap` structurally cannot: that property only ever
+/// sees a single generated snapshot of regions, never a sequence of
+/// allocations and frees against shared, evolving allocator state.
+pub fn prop_allocator_model_matches_reference(seq: OpSequence) -> TestResult {
+    const ARENA_SIZE: u64 = 64 * 1024 * 1024;
+
+    let mut model = ReferenceAllocator::new(ARENA_SIZE);
+    // `live` mirrors the handles returned by the allocator under test, in
+    // allocation order, so `Op::Free { live_index }` can address them the
+    // same way the generator indexed them. Each entry carries a unique
+    // `id` (not just its address) so a later allocation that legitimately
+    // reuses a freed address via first-fit is never confused with the
+    // handle that used to live there.
+    let mut live: Vec<(u64, u64, u64, u64)> = Vec::new(); // (start, size, align, id)
+    let mut freed_ids: Vec<u64> = Vec::new();
+    let mut next_id = 0u64;
+
+    for op in &seq.0 {
+        match *op {
+            Op::Alloc { size, align } => {
+                let Some(start) = model.alloc(size, align) else {
+                    // Arena exhausted; not a bug, just stop this sequence.
+                    return TestResult::discard();
+                };
+
+                if start % align != 0 {
+                    return TestResult::error(format!(
+                        "allocation at {:#x} violates alignment {}",
+                        start, align
+                    ));
+                }
+                if start + size > ARENA_SIZE {
+                    return TestResult::error("allocation escaped the managed arena");
+                }
+
+                for &(other_start, other_size, _, _) in &live {
+                    let overlaps = start < other_start + other_size && other_start < start + size;
+                    if overlaps {
+                        return TestResult::error(format!(
+                            "new allocation [{:#x}, {:#x}) overlaps live block [{:#x}, {:#x})",
+                            start,
+                            start + size,
+                            other_start,
+                            other_start + other_size
+                        ));
+                    }
+                }
+
+                live.push((start, size, align, next_id));
+                next_id += 1;
+            }
+            Op::Free { live_index } => {
+                if live_index >= live.len() {
+                    return TestResult::discard();
+                }
+                let (start, _, _, id) = live.remove(live_index);
+
+                if freed_ids.contains(&id) {
+                    return TestResult::error("double-free was not rejected");
+                }
+                if !model.free(start) {
+                    return TestResult::error("freeing a live handle was rejected");
+                }
+                freed_ids.push(id);
+
+                // Freeing the same handle a second time must be rejected.
+                if model.free(start) {
+                    return TestResult::error("double-free was not rejected");
+                }
+            }
+        }
+    }
+
+    TestResult::passed()
+}
+
+/// A node in a generated multi-level page table, indexed at each level by 9
+/// VPN bits (512-way). Only the 4 levels x86_64/Sv48 need (and the 3 Sv39
+/// needs) are ever created, since nodes are allocated lazily by `install`.
+#[derive(Default)]
+struct PageTableNode {
+    children: HashMap<u64, PageTableNode>,
+    leaf_ppn: Option<u64>,
+}
+
+/// A generated page table for one architecture, walked exactly as hardware
+/// would: `levels()` successive 9-bit VPN indices select a child table, and
+/// the final level's entry holds the mapped physical page number.
+struct PageTable {
+    arch: Arch,
+    root: PageTableNode,
+}
+
+impl PageTable {
+    fn new(arch: Arch) -> Self {
+        PageTable { arch, root: PageTableNode::default() }
+    }
+
+    /// The 9-bit VPN index a walk uses at `level` (0 = top-level table).
+    fn vpn_index(&self, va: u64, level: u32) -> u64 {
+        Self::vpn_index_for(self.arch, va, level)
+    }
+
+    /// Same computation as `vpn_index`, but taking `arch` by value so it
+    /// can be called without holding a borrow of the `PageTable` itself —
+    /// needed by `insert_at`, which already holds a mutable borrow of one
+    /// of the table's nodes.
+    fn vpn_index_for(arch: Arch, va: u64, level: u32) -> u64 {
+        let shift = 12 + 9 * (arch.levels() - 1 - level);
+        (va >> shift) & 0x1FF
+    }
+
+    /// Installs a leaf mapping `va -> ppn`, creating intermediate
+    /// page-table nodes on demand.
+    ///
+    /// Written as recursion over `&mut PageTableNode` rather than a loop
+    /// that reassigns a `node: &mut PageTableNode` binding from inside
+    /// `node.children.entry(...)`: the borrow checker can't see that each
+    /// loop iteration's borrow of `node` ends before the next begins, so
+    /// that version doesn't compile on stable Rust.
+    fn install(&mut self, va: u64, ppn: u64) {
+        let levels = self.arch.levels();
+        let arch = self.arch;
+        Self::insert_at(&mut self.root, arch, va, ppn, 0, levels);
+    }
+
+    fn insert_at(node: &mut PageTableNode, arch: Arch, va: u64, ppn: u64, level: u32, levels: u32) {
+        let idx = Self::vpn_index_for(arch, va, level);
+        let child = node.children.entry(idx).or_default();
+
+        if level == levels - 1 {
+            child.leaf_ppn = Some(ppn);
+        } else {
+            Self::insert_at(child, arch, va, ppn, level + 1, levels);
+        }
+    }
+
+    /// Walks the table for `va`, returning the translated physical address
+    /// (mapped PPN with the page offset preserved) or `None` if any level
+    /// of the walk is unmapped — the hardware equivalent of a page fault.
+    fn walk(&self, va: u64) -> Option<u64> {
+        let levels = self.arch.levels();
+        let mut node = &self.root;
+
+        for level in 0..levels {
+            let idx = self.vpn_index(va, level);
+            node = node.children.get(&idx)?;
+
+            if level == levels - 1 {
+                let ppn = node.leaf_ppn?;
+                return Some((ppn << 12) | (va & 0xFFF));
+            }
+        }
+
+        None
+    }
+}
+
+/// A random page table plus a query address that is either one of the
+/// installed mappings or an arbitrary canonical address likely to be
+/// unmapped, so the property below exercises both the hit and fault paths.
+#[derive(Clone, Debug)]
+pub struct PageTableFixture {
+    pub arch: Arch,
+    pub mappings: Vec<(u64, u64)>,
+    pub query: u64,
+}
+
+impl Arbitrary for PageTableFixture {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let arch = Arch::arbitrary(g);
+        let count = (usize::arbitrary(g) % 8) + 1;
+
+        let mappings: Vec<(u64, u64)> = (0..count)
+            .map(|_| {
+                let va = canonical_address(arch, g);
+                let ppn = u64::arbitrary(g) % (1u64 << 40);
+                (va, ppn)
+            })
+            .collect();
+
+        let query = if bool::arbitrary(g) {
+            mappings[usize::arbitrary(g) % mappings.len()].0
+        } else {
+            canonical_address(arch, g)
+        };
+
+        PageTableFixture { arch, mappings, query }
+    }
+}
+
+/// Property: walking a generated page table for a mapped virtual address
+/// yields the installed physical page with the low 12 offset bits
+/// preserved, and walking an unmapped address faults instead of aliasing
+/// onto some other mapping.
+///
+/// This replaces the previous XOR mock, which couldn't model the RISC-V
+/// Sv39/Sv48 layouts this crate targets and "verified" a translation
+/// scheme against itself rather than a real multi-level walk.
+pub fn prop_address_translation_reversible(fixture: PageTableFixture) -> TestResult {
+    let mut table = PageTable::new(fixture.arch);
+    // Later installs to the same page win, matching table semantics.
+    let mut expected: HashMap<u64, u64> = HashMap::new();
+
+    for &(va, ppn) in &fixture.mappings {
+        table.install(va, ppn);
+        expected.insert(va & !0xFFF, ppn);
+    }
+
+    let query_page = fixture.query & !0xFFF;
+    let walked = table.walk(fixture.query);
+
+    match (expected.get(&query_page), walked) {
+        (Some(&exp_ppn), Some(translated)) => TestResult::from_bool(
+            translated >> 12 == exp_ppn && translated & 0xFFF == fixture.query & 0xFFF,
+        ),
+        (None, None) => TestResult::passed(),
+        (Some(_), None) => TestResult::error("mapped virtual address faulted"),
+        (None, Some(_)) => TestResult::error("unmapped virtual address aliased onto a mapping"),
+    }
 }
 
 /// Property: Page allocation should satisfy alignment requirements.
@@ -283,7 +731,7 @@ mod tests {
     
     #[test]
     fn test_address_translation() {
-        quickcheck(prop_address_translation_reversible as fn(VirtualAddress) -> bool);
+        quickcheck(prop_address_translation_reversible as fn(PageTableFixture) -> TestResult);
     }
     
     #[test]
@@ -295,6 +743,11 @@ mod tests {
     fn test_no_overlap() {
         quickcheck(prop_no_memory_overlap as fn(Vec<MemoryRegion>) -> TestResult);
     }
+
+    #[test]
+    fn test_allocator_model() {
+        quickcheck(prop_allocator_model_matches_reference as fn(OpSequence) -> TestResult);
+    }
     
     #[test]
     fn test_refcount() {